@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use futures::executor::block_on;
 use winit::{
     dpi::PhysicalSize,
@@ -6,10 +8,18 @@ use winit::{
     window::WindowBuilder,
 };
 
-use crate::{Frame, RenderInstance, Renderer};
+use crate::{Frame, RenderInstance, Renderer, Viewport};
 
 #[allow(unused)]
 pub trait State {
+    /// Called once per frame before drawing, with the seconds since the last update.
+    fn update(&mut self, delta_time: f32) {}
+
+    /// Called for every window event, `viewport` covers the whole window.
+    fn event(&mut self, event: &WindowEvent<'_>, viewport: &Viewport) {}
+
+    /// Called once per frame to draw, the stats of the previous frame are in
+    /// [`Frame::last_stats`].
     fn draw<'a>(&'a mut self, frame: &mut Frame<'a>) {}
 }
 
@@ -62,10 +72,11 @@ impl App {
         let (instance, swapchain) = block_on(RenderInstance::new(&window)).unwrap();
 
         let mut renderer = Renderer::new(&instance, swapchain);
+        let mut last_update = Instant::now();
 
         event_loop.run(move |event, _, control_flow| match event {
             Event::RedrawRequested(_) => {
-                let mut render_frame = Frame::new(renderer.aspect());
+                let mut render_frame = renderer.frame();
 
                 state.draw(&mut render_frame);
 
@@ -81,23 +92,31 @@ impl App {
                 }
             }
             Event::MainEventsCleared => {
+                let now = Instant::now();
+                state.update((now - last_update).as_secs_f32());
+                last_update = now;
+
                 window.request_redraw();
             }
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => {
-                    *control_flow = ControlFlow::Exit;
-                }
-                WindowEvent::Resized(size) => {
-                    renderer.resize(size.width, size.height);
-                }
-                WindowEvent::ScaleFactorChanged {
-                    new_inner_size: size,
-                    ..
-                } => {
-                    renderer.resize(size.width, size.height);
+            Event::WindowEvent { event, .. } => {
+                state.event(&event, &Viewport::new(renderer.width(), renderer.height()));
+
+                match event {
+                    WindowEvent::CloseRequested => {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    WindowEvent::Resized(size) => {
+                        renderer.resize(size.width, size.height);
+                    }
+                    WindowEvent::ScaleFactorChanged {
+                        new_inner_size: size,
+                        ..
+                    } => {
+                        renderer.resize(size.width, size.height);
+                    }
+                    _ => {}
                 }
-                _ => {}
-            },
+            }
             _ => {}
         })
     }
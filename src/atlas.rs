@@ -0,0 +1,360 @@
+use glam::Vec2;
+
+use crate::{Rect, RenderInstance, SpriteOptions, Texture};
+
+/// Handle to an image added to a [`TextureAtlas`].
+///
+/// Handles stay valid until the atlas is cleared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasRegion(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasEntry {
+    /// Index of the page texture the region lives in.
+    pub page: usize,
+    /// Position in pixels on the page.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Uv coordinates on the page.
+    pub uv: Rect,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Start of the free space on the shelf.
+    x: u32,
+}
+
+/// Packs rectangles into rows of fixed height, called shelves.
+///
+/// Each allocation is placed on the shelf that wastes the least height, a new shelf is
+/// opened below the last one if none fit.
+#[derive(Clone, Debug)]
+pub struct ShelfPacker {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+    next_y: u32,
+}
+
+impl ShelfPacker {
+    #[inline]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            shelves: Vec::new(),
+            next_y: 0,
+        }
+    }
+
+    /// Allocates a `width` by `height` rectangle, returning its top left corner or
+    /// [`None`] if it doesn't fit.
+    #[inline]
+    pub fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > self.width || height > self.height {
+            return None;
+        }
+
+        let page_width = self.width;
+
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && page_width - shelf.x >= width)
+            // don't put small rectangles on much taller shelves
+            .filter(|shelf| shelf.height <= height * 2 || shelf.height <= 8)
+            .min_by_key(|shelf| shelf.height - height);
+
+        if let Some(shelf) = best {
+            let position = (shelf.x, shelf.y);
+            shelf.x += width;
+            return Some(position);
+        }
+
+        if self.height - self.next_y < height {
+            return None;
+        }
+
+        let y = self.next_y;
+        self.next_y += height;
+        self.shelves.push(Shelf {
+            y,
+            height,
+            x: width,
+        });
+
+        Some((0, y))
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.shelves.clear();
+        self.next_y = 0;
+    }
+}
+
+/// Packs many small images into a few large textures.
+///
+/// A new page is added when an image doesn't fit in any of the existing ones, images
+/// larger than the page size get a page of their own.
+#[derive(Debug)]
+pub struct TextureAtlas {
+    page_size: u32,
+    /// Empty pixels between regions, prevents neighbours from bleeding in when filtering.
+    padding: u32,
+    pages: Vec<Texture>,
+    /// Packer of every page, in the same order.
+    packers: Vec<ShelfPacker>,
+    regions: Vec<AtlasEntry>,
+}
+
+impl Default for TextureAtlas {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PAGE_SIZE)
+    }
+}
+
+impl TextureAtlas {
+    pub const DEFAULT_PAGE_SIZE: u32 = 1024;
+
+    #[inline]
+    pub fn new(page_size: u32) -> Self {
+        Self {
+            page_size,
+            padding: 1,
+            pages: Vec::new(),
+            packers: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Adds a tightly packed rgba8 image to the atlas.
+    #[inline]
+    pub fn add(
+        &mut self,
+        instance: &RenderInstance,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> AtlasRegion {
+        assert_eq!(
+            data.len(),
+            width as usize * height as usize * 4,
+            "image data must be width * height * 4 bytes"
+        );
+
+        let padded_width = width + self.padding * 2;
+        let padded_height = height + self.padding * 2;
+
+        let (page, x, y) = allocate(
+            &mut self.packers,
+            self.page_size,
+            padded_width,
+            padded_height,
+        );
+
+        if page == self.pages.len() {
+            let packer = &self.packers[page];
+
+            // clear the page so padding stays transparent
+            let empty = vec![0; packer.width as usize * packer.height as usize * 4];
+
+            self.pages.push(Texture::from_rgba(
+                instance,
+                packer.width,
+                packer.height,
+                &empty,
+            ));
+        }
+
+        let x = x + self.padding;
+        let y = y + self.padding;
+
+        let texture = &self.pages[page];
+        texture.write(instance, x, y, width, height, data);
+
+        let min = Vec2::new(x as f32, y as f32);
+        let size = Vec2::new(width as f32, height as f32);
+
+        self.regions.push(AtlasEntry {
+            page,
+            x,
+            y,
+            width,
+            height,
+            uv: texture.uv_rect(Rect::from_min_size(min, size)),
+        });
+
+        AtlasRegion(self.regions.len() - 1)
+    }
+
+    #[inline]
+    pub fn get(&self, region: AtlasRegion) -> &AtlasEntry {
+        &self.regions[region.0]
+    }
+
+    /// Uv coordinates of `region` on its page.
+    #[inline]
+    pub fn uv(&self, region: AtlasRegion) -> Rect {
+        self.get(region).uv
+    }
+
+    /// The page texture `region` lives in.
+    #[inline]
+    pub fn texture(&self, region: AtlasRegion) -> &Texture {
+        &self.pages[self.get(region).page]
+    }
+
+    /// Returns the page texture of `region` and `options` with the source set to the region.
+    ///
+    /// `options.source` is interpreted relative to the region.
+    #[inline]
+    pub fn sprite(
+        &self,
+        region: AtlasRegion,
+        mut options: SpriteOptions,
+    ) -> (&Texture, SpriteOptions) {
+        let uv = self.uv(region);
+
+        options.source = Rect::new(
+            uv.min + options.source.min * uv.size(),
+            uv.min + options.source.max * uv.size(),
+        );
+
+        (self.texture(region), options)
+    }
+
+    #[inline]
+    pub fn page(&self, index: usize) -> &Texture {
+        &self.pages[index]
+    }
+
+    #[inline]
+    pub fn pages(&self) -> impl Iterator<Item = &Texture> {
+        self.pages.iter()
+    }
+
+    /// Removes every image and drops the pages, invalidating all regions.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.packers.clear();
+        self.regions.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+/// Allocates a `width` by `height` rectangle on the first page of `packers` it fits on,
+/// returning the page and the top left corner. A new page is added when it fits on none,
+/// at least `page_size` large.
+#[inline]
+fn allocate(
+    packers: &mut Vec<ShelfPacker>,
+    page_size: u32,
+    width: u32,
+    height: u32,
+) -> (usize, u32, u32) {
+    let allocation = packers.iter_mut().enumerate().find_map(|(i, packer)| {
+        let (x, y) = packer.allocate(width, height)?;
+        Some((i, x, y))
+    });
+
+    if let Some(allocation) = allocation {
+        return allocation;
+    }
+
+    let mut packer = ShelfPacker::new(page_size.max(width), page_size.max(height));
+    let (x, y) = packer.allocate(width, height).unwrap();
+    packers.push(packer);
+
+    (packers.len() - 1, x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shelves_are_reused() {
+        let mut packer = ShelfPacker::new(100, 100);
+
+        assert_eq!(packer.allocate(40, 20), Some((0, 0)));
+        // fits next to the first rectangle
+        assert_eq!(packer.allocate(40, 16), Some((40, 0)));
+        // too wide for the rest of the shelf
+        assert_eq!(packer.allocate(40, 20), Some((0, 20)));
+        assert_eq!(packer.allocate(20, 20), Some((80, 0)));
+    }
+
+    #[test]
+    fn shelves_are_opened_below() {
+        let mut packer = ShelfPacker::new(100, 100);
+
+        assert_eq!(packer.allocate(10, 10), Some((0, 0)));
+        // much taller than the first shelf
+        assert_eq!(packer.allocate(10, 30), Some((0, 10)));
+        // much shorter than the second shelf, the first one has room
+        assert_eq!(packer.allocate(10, 8), Some((10, 0)));
+        // wastes the least height on the second shelf
+        assert_eq!(packer.allocate(10, 25), Some((10, 10)));
+    }
+
+    #[test]
+    fn full_pages_allocate_nothing() {
+        let mut packer = ShelfPacker::new(100, 100);
+
+        assert_eq!(packer.allocate(101, 10), None);
+        assert_eq!(packer.allocate(10, 101), None);
+        assert_eq!(packer.allocate(100, 60), Some((0, 0)));
+        assert_eq!(packer.allocate(100, 40), Some((0, 60)));
+        assert_eq!(packer.allocate(1, 1), None);
+
+        packer.clear();
+        assert_eq!(packer.allocate(100, 100), Some((0, 0)));
+    }
+
+    #[test]
+    fn pages_are_added_when_full() {
+        let mut packers = Vec::new();
+
+        assert_eq!(allocate(&mut packers, 100, 100, 60), (0, 0, 0));
+        assert_eq!(allocate(&mut packers, 100, 100, 60), (1, 0, 0));
+        // the first page still has room
+        assert_eq!(allocate(&mut packers, 100, 50, 40), (0, 0, 60));
+        assert_eq!(packers.len(), 2);
+    }
+
+    #[test]
+    fn oversized_images_get_their_own_page() {
+        let mut packers = Vec::new();
+
+        assert_eq!(allocate(&mut packers, 100, 10, 10), (0, 0, 0));
+        assert_eq!(allocate(&mut packers, 100, 300, 20), (1, 0, 0));
+        assert_eq!((packers[1].width, packers[1].height), (300, 100));
+        assert_eq!(allocate(&mut packers, 100, 50, 200), (2, 0, 0));
+        assert_eq!((packers[2].width, packers[2].height), (100, 200));
+        // small images still go on the first page
+        assert_eq!(allocate(&mut packers, 100, 10, 10), (0, 10, 0));
+    }
+}
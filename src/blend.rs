@@ -0,0 +1,199 @@
+/// How the colors of a draw are combined with what's already drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Alpha blending.
+    Normal,
+    /// Alpha blending of colors already multiplied by their alpha.
+    Premultiplied,
+    /// Adds the color, scaled by alpha, used for glows and lights.
+    Additive,
+    /// Multiplies with the color, used for shadows and tinting.
+    ///
+    /// The color is premultiplied before blending, so transparent pixels leave the
+    /// target unchanged.
+    Multiply,
+    /// Inverse of multiplying the inverted colors, brightens.
+    ///
+    /// The color is premultiplied before blending, like [`BlendMode::Multiply`].
+    Screen,
+    /// Replaces the color, ignoring alpha.
+    Opaque,
+}
+
+impl Default for BlendMode {
+    #[inline]
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 6] = [
+        Self::Normal,
+        Self::Premultiplied,
+        Self::Additive,
+        Self::Multiply,
+        Self::Screen,
+        Self::Opaque,
+    ];
+
+    /// Whether the color is multiplied by its alpha before blending, the blend states of
+    /// these modes expect premultiplied colors.
+    #[inline]
+    pub(crate) fn premultiplies(self) -> bool {
+        matches!(self, Self::Multiply | Self::Screen)
+    }
+
+    /// Prepends `fn output(color: vec4<f32>) -> vec4<f32>` to `source`, fragment shaders
+    /// pass the straight alpha color they return through it.
+    #[inline]
+    pub(crate) fn shader_source(self, source: &str) -> String {
+        let output = if self.premultiplies() {
+            "fn output(color: vec4<f32>) -> vec4<f32> {\n\treturn vec4<f32>(color.rgb * color.a, color.a);\n}\n\n"
+        } else {
+            "fn output(color: vec4<f32>) -> vec4<f32> {\n\treturn color;\n}\n\n"
+        };
+
+        format!("{}{}", output, source)
+    }
+
+    #[inline]
+    pub(crate) fn blend_state(self) -> Option<wgpu::BlendState> {
+        fn component(
+            src_factor: wgpu::BlendFactor,
+            dst_factor: wgpu::BlendFactor,
+        ) -> wgpu::BlendComponent {
+            wgpu::BlendComponent {
+                src_factor,
+                dst_factor,
+                operation: wgpu::BlendOperation::Add,
+            }
+        }
+
+        // alpha accumulates the same way for every mode
+        let alpha = component(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha);
+
+        let color = match self {
+            Self::Normal => component(
+                wgpu::BlendFactor::SrcAlpha,
+                wgpu::BlendFactor::OneMinusSrcAlpha,
+            ),
+            Self::Premultiplied => {
+                component(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha)
+            }
+            Self::Additive => component(wgpu::BlendFactor::SrcAlpha, wgpu::BlendFactor::One),
+            Self::Multiply => {
+                component(wgpu::BlendFactor::Dst, wgpu::BlendFactor::OneMinusSrcAlpha)
+            }
+            Self::Screen => component(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrc),
+            Self::Opaque => return None,
+        };
+
+        Some(wgpu::BlendState { color, alpha })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Blends a straight alpha `src` color onto `dst` on the cpu, the way the gpu does.
+    fn blend(mode: BlendMode, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+        let state = match mode.blend_state() {
+            Some(state) => state,
+            None => return src,
+        };
+
+        let src = if mode.premultiplies() {
+            [src[0] * src[3], src[1] * src[3], src[2] * src[3], src[3]]
+        } else {
+            src
+        };
+
+        let factor = |factor: wgpu::BlendFactor, channel: usize| match factor {
+            wgpu::BlendFactor::Zero => 0.0,
+            wgpu::BlendFactor::One => 1.0,
+            wgpu::BlendFactor::Src => src[channel],
+            wgpu::BlendFactor::OneMinusSrc => 1.0 - src[channel],
+            wgpu::BlendFactor::SrcAlpha => src[3],
+            wgpu::BlendFactor::OneMinusSrcAlpha => 1.0 - src[3],
+            wgpu::BlendFactor::Dst => dst[channel],
+            wgpu::BlendFactor::OneMinusDst => 1.0 - dst[channel],
+            factor => unreachable!("no blend mode uses {:?}", factor),
+        };
+
+        let mut out = [0.0; 4];
+
+        for channel in 0..4 {
+            let component = if channel == 3 {
+                state.alpha
+            } else {
+                state.color
+            };
+
+            assert_eq!(component.operation, wgpu::BlendOperation::Add);
+
+            out[channel] = src[channel] * factor(component.src_factor, channel)
+                + dst[channel] * factor(component.dst_factor, channel);
+        }
+
+        out
+    }
+
+    fn assert_close(a: [f32; 4], b: [f32; 4]) {
+        for (a, b) in a.iter().zip(&b) {
+            assert!((a - b).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    const DST: [f32; 4] = [0.2, 0.4, 0.8, 1.0];
+
+    #[test]
+    fn multiply_transparent_source_keeps_target() {
+        assert_close(blend(BlendMode::Multiply, [1.0, 1.0, 1.0, 0.0], DST), DST);
+        assert_close(blend(BlendMode::Multiply, [0.0, 0.0, 0.0, 0.0], DST), DST);
+    }
+
+    #[test]
+    fn multiply_translucent_source() {
+        // half way between the target and the target multiplied by grey
+        let out = blend(BlendMode::Multiply, [0.5, 0.5, 0.5, 0.5], DST);
+
+        assert_close(out, [0.15, 0.3, 0.6, 1.0]);
+    }
+
+    #[test]
+    fn multiply_opaque_source() {
+        let out = blend(BlendMode::Multiply, [0.5, 1.0, 0.0, 1.0], DST);
+
+        assert_close(out, [0.1, 0.4, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn screen_transparent_source_keeps_target() {
+        assert_close(blend(BlendMode::Screen, [1.0, 1.0, 1.0, 0.0], DST), DST);
+    }
+
+    #[test]
+    fn screen_translucent_source() {
+        let screen = |s: f32, d: f32| 1.0 - (1.0 - s) * (1.0 - d);
+        let out = blend(BlendMode::Screen, [0.5, 0.5, 0.5, 0.5], DST);
+
+        assert_close(
+            out,
+            [
+                (DST[0] + screen(0.5, DST[0])) / 2.0,
+                (DST[1] + screen(0.5, DST[1])) / 2.0,
+                (DST[2] + screen(0.5, DST[2])) / 2.0,
+                1.0,
+            ],
+        );
+    }
+
+    #[test]
+    fn normal_translucent_source() {
+        let out = blend(BlendMode::Normal, [1.0, 0.0, 0.0, 0.5], DST);
+
+        assert_close(out, [0.6, 0.2, 0.4, 1.0]);
+    }
+}
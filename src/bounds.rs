@@ -0,0 +1,85 @@
+use glam::{Mat4, Vec3, Vec4};
+use scissor::mesh::Mesh;
+
+use crate::Rect;
+
+/// An axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    #[inline]
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box containing `points`, `None` if there are none.
+    #[inline]
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, point| {
+            Some(match bounds {
+                Some(Self { min, max }) => Self::new(min.min(point), max.max(point)),
+                None => Self::new(point, point),
+            })
+        })
+    }
+
+    /// Bounds of the vertices of `mesh`, `None` if it has none.
+    #[inline]
+    pub fn from_mesh(mesh: &Mesh) -> Option<Self> {
+        let vertices: &[[f32; 7]] = bytemuck::cast_slice(&mesh.vertices);
+
+        Self::from_points(
+            vertices
+                .iter()
+                .map(|vertex| Vec3::new(vertex[0], vertex[1], vertex[2])),
+        )
+    }
+
+    /// A flat box covering `rect` on the plane z = 0.
+    #[inline]
+    pub fn from_rect(rect: Rect) -> Self {
+        Self::new(rect.min.extend(0.0), rect.max.extend(0.0))
+    }
+
+    #[inline]
+    pub fn corners(&self) -> [Vec3; 8] {
+        let Self { min, max } = *self;
+
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// Whether the box, transformed by `matrix` into clip space, may be visible.
+    ///
+    /// Conservative, boxes near the corners of the frustum may pass while outside.
+    #[inline]
+    pub fn in_frustum(&self, matrix: Mat4) -> bool {
+        let mut clip = [Vec4::ZERO; 8];
+
+        for (clip, corner) in clip.iter_mut().zip(&self.corners()) {
+            *clip = matrix * corner.extend(1.0);
+        }
+
+        // outside if every corner is beyond the same plane
+        let outside = |beyond: fn(Vec4) -> bool| clip.iter().all(|&point| beyond(point));
+
+        !(outside(|p| p.x < -p.w)
+            || outside(|p| p.x > p.w)
+            || outside(|p| p.y < -p.w)
+            || outside(|p| p.y > p.w)
+            || outside(|p| p.z < 0.0)
+            || outside(|p| p.z > p.w))
+    }
+}
@@ -0,0 +1,411 @@
+use glam::{Mat4, Vec2, Vec3, Vec4};
+
+use crate::{Rect, Transform};
+
+#[derive(Clone, Debug)]
+pub enum ScaleMode {
+    /// Scale camera to be (aspect * size, size).
+    Aspect,
+    /// Don't scale the camera.
+    None,
+}
+
+#[derive(Clone, Debug)]
+pub struct OrthographicCamera {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+    pub near: f32,
+    pub far: f32,
+    pub size: f32,
+    pub scale_mode: ScaleMode,
+    /// Position, rotation and scale of the camera in world units, see
+    /// [`Camera::view_proj`].
+    pub transform: Transform,
+}
+
+impl Default for OrthographicCamera {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            left: -1.0,
+            bottom: -1.0,
+            right: 1.0,
+            top: 1.0,
+            near: -500.0,
+            far: 500.0,
+            size: 2.0,
+            scale_mode: ScaleMode::Aspect,
+            transform: Transform::IDENTITY,
+        }
+    }
+}
+
+/// Maps world space to clip space, used by every draw.
+pub trait Camera {
+    /// The transform of the camera in world space.
+    fn view(&self) -> Mat4;
+
+    /// Projection of view space to clip space for a target of `aspect`.
+    fn proj(&self, aspect: f32) -> Mat4;
+
+    /// Maps world space to clip space, the inverse of [`Camera::view`] followed by the
+    /// projection.
+    #[inline]
+    fn view_proj(&self, aspect: f32) -> Mat4 {
+        self.proj(aspect) * self.view().inverse()
+    }
+
+    /// The ray through `pixel` of `viewport`, from the near to the far plane.
+    #[inline]
+    fn ray(&self, pixel: Vec2, viewport: &Viewport) -> Ray {
+        let inverse = self.view_proj(viewport.aspect()).inverse();
+        let ndc = viewport.pixel_to_ndc(pixel);
+
+        let unproject = |z: f32| {
+            let point = inverse * Vec4::new(ndc.x, ndc.y, z, 1.0);
+            point.truncate() / point.w
+        };
+
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+
+        Ray {
+            origin: near,
+            direction: (far - near).normalize(),
+        }
+    }
+
+    /// The point on the plane z = 0 under `pixel` of `viewport`.
+    ///
+    /// Falls back to the start of the [`Camera::ray`] when it runs parallel to the
+    /// plane.
+    #[inline]
+    fn screen_to_world(&self, pixel: Vec2, viewport: &Viewport) -> Vec2 {
+        let ray = self.ray(pixel, viewport);
+
+        ray.plane_intersection(Vec3::ZERO, Vec3::Z)
+            .unwrap_or(ray.origin)
+            .truncate()
+    }
+
+    /// The pixel of `viewport` `point` is drawn at.
+    #[inline]
+    fn world_to_screen(&self, point: Vec3, viewport: &Viewport) -> Vec2 {
+        let clip = self.view_proj(viewport.aspect()) * point.extend(1.0);
+
+        viewport.ndc_to_pixel(Vec2::new(clip.x, clip.y) / clip.w)
+    }
+
+    /// The rectangle of the plane z = 0 visible on a target of `aspect`.
+    #[inline]
+    fn world_bounds(&self, aspect: f32) -> Rect {
+        let viewport = Viewport::from_rect(Rect::new(Vec2::ZERO, Vec2::new(aspect, 1.0)));
+
+        let corners = [
+            Vec2::ZERO,
+            Vec2::new(aspect, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(aspect, 1.0),
+        ];
+
+        corners.iter().fold(
+            Rect::new(Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |bounds, &corner| {
+                let point = self.screen_to_world(corner, &viewport);
+                Rect::new(bounds.min.min(point), bounds.max.max(point))
+            },
+        )
+    }
+}
+
+/// A rectangle of the target in pixels, from the top left corner with y pointing down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub rect: Rect,
+}
+
+impl Viewport {
+    /// The whole of a `width` by `height` target.
+    #[inline]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::from_rect(Rect::new(
+            Vec2::ZERO,
+            Vec2::new(width as f32, height as f32),
+        ))
+    }
+
+    #[inline]
+    pub fn from_rect(rect: Rect) -> Self {
+        Self { rect }
+    }
+
+    #[inline]
+    pub fn aspect(&self) -> f32 {
+        self.rect.width() / self.rect.height()
+    }
+
+    /// Converts a pixel to normalized device coordinates, y pointing up.
+    #[inline]
+    pub fn pixel_to_ndc(&self, pixel: Vec2) -> Vec2 {
+        let uv = (pixel - self.rect.min) / self.rect.size();
+
+        Vec2::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0)
+    }
+
+    /// Converts normalized device coordinates to a pixel.
+    #[inline]
+    pub fn ndc_to_pixel(&self, ndc: Vec2) -> Vec2 {
+        let uv = Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) / 2.0;
+
+        self.rect.min + uv * self.rect.size()
+    }
+}
+
+/// A half line in world space, see [`Camera::ray`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Normalized direction.
+    pub direction: Vec3,
+}
+
+impl Ray {
+    #[inline]
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Where the ray hits the plane through `point` facing `normal`, if it does.
+    #[inline]
+    pub fn plane_intersection(&self, point: Vec3, normal: Vec3) -> Option<Vec3> {
+        let denominator = self.direction.dot(normal);
+
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let distance = (point - self.origin).dot(normal) / denominator;
+
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(self.at(distance))
+    }
+}
+
+impl Camera for OrthographicCamera {
+    #[inline]
+    fn proj(&self, aspect: f32) -> Mat4 {
+        let mut camera = self.clone();
+
+        match self.scale_mode {
+            ScaleMode::Aspect => {
+                camera.left = -camera.size * aspect / 2.0;
+                camera.right = camera.size * aspect / 2.0;
+                camera.bottom = -camera.size / 2.0;
+                camera.top = camera.size / 2.0;
+            }
+            ScaleMode::None => {}
+        }
+
+        Mat4::orthographic_rh(
+            camera.left,
+            camera.right,
+            camera.bottom,
+            camera.top,
+            camera.near,
+            camera.far,
+        )
+    }
+
+    #[inline]
+    fn view(&self) -> Mat4 {
+        self.transform.matrix()
+    }
+}
+
+/// A camera with perspective, looking down its negative z axis.
+#[derive(Clone, Debug)]
+pub struct PerspectiveCamera {
+    /// Vertical field of view in radians.
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+    pub transform: Transform,
+}
+
+impl Default for PerspectiveCamera {
+    /// Shows the same two units tall area of the plane z = 0 as the default
+    /// [`OrthographicCamera`].
+    #[inline]
+    fn default() -> Self {
+        let fov = std::f32::consts::FRAC_PI_3;
+
+        Self {
+            fov,
+            near: 0.1,
+            far: 1000.0,
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0 / (fov / 2.0).tan())),
+        }
+    }
+}
+
+impl PerspectiveCamera {
+    /// Rotates the camera to face `target`, with `up` pointing up on screen.
+    #[inline]
+    pub fn look_at(mut self, target: Vec3, up: Vec3) -> Self {
+        let view = Mat4::look_at_rh(self.transform.translation, target, up);
+        let (_, rotation, _) = view.inverse().to_scale_rotation_translation();
+
+        self.transform.rotation = rotation;
+        self
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    #[inline]
+    fn proj(&self, aspect: f32) -> Mat4 {
+        Mat4::perspective_rh(self.fov, aspect, self.near, self.far)
+    }
+
+    #[inline]
+    fn view(&self) -> Mat4 {
+        self.transform.matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).abs().max_element() < 1e-3, "{} != {}", a, b);
+    }
+
+    /// 400 by 300 pixels, offset from the corner of the target.
+    fn viewport() -> Viewport {
+        Viewport::from_rect(Rect::new(Vec2::new(100.0, 50.0), Vec2::new(500.0, 350.0)))
+    }
+
+    /// Pixels around the viewport, including its corners and center.
+    fn pixels() -> Vec<Vec2> {
+        vec![
+            Vec2::new(100.0, 50.0),
+            Vec2::new(500.0, 350.0),
+            Vec2::new(300.0, 200.0),
+            Vec2::new(120.0, 330.0),
+            Vec2::new(0.0, 0.0),
+        ]
+    }
+
+    /// Camera moved to (10, -5) seeing twice as much of the world.
+    fn moved_camera() -> OrthographicCamera {
+        OrthographicCamera {
+            transform: Transform {
+                translation: Vec3::new(10.0, -5.0, 0.0),
+                scale: Vec3::new(2.0, 2.0, 1.0),
+                ..Transform::IDENTITY
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ndc_flips_y() {
+        let viewport = viewport();
+
+        assert_close(
+            viewport.pixel_to_ndc(Vec2::new(100.0, 50.0)),
+            Vec2::new(-1.0, 1.0),
+        );
+        assert_close(
+            viewport.pixel_to_ndc(Vec2::new(500.0, 350.0)),
+            Vec2::new(1.0, -1.0),
+        );
+        assert_close(viewport.pixel_to_ndc(Vec2::new(300.0, 200.0)), Vec2::ZERO);
+        assert_close(
+            viewport.ndc_to_pixel(Vec2::new(-1.0, 1.0)),
+            Vec2::new(100.0, 50.0),
+        );
+    }
+
+    #[test]
+    fn ndc_round_trip() {
+        let viewport = viewport();
+
+        for pixel in pixels() {
+            assert_close(viewport.ndc_to_pixel(viewport.pixel_to_ndc(pixel)), pixel);
+        }
+    }
+
+    #[test]
+    fn aspect_camera_screen_to_world() {
+        let camera = OrthographicCamera::default();
+        let viewport = viewport();
+
+        // 4:3 viewport, the camera is 2 units high
+        assert_close(
+            camera.screen_to_world(Vec2::new(300.0, 200.0), &viewport),
+            Vec2::ZERO,
+        );
+        assert_close(
+            camera.screen_to_world(Vec2::new(100.0, 50.0), &viewport),
+            Vec2::new(-4.0 / 3.0, 1.0),
+        );
+        assert_close(
+            camera.screen_to_world(Vec2::new(500.0, 350.0), &viewport),
+            Vec2::new(4.0 / 3.0, -1.0),
+        );
+    }
+
+    #[test]
+    fn moved_camera_screen_to_world() {
+        let camera = moved_camera();
+        let viewport = viewport();
+
+        assert_close(
+            camera.screen_to_world(Vec2::new(300.0, 200.0), &viewport),
+            Vec2::new(10.0, -5.0),
+        );
+        assert_close(
+            camera.screen_to_world(Vec2::new(100.0, 50.0), &viewport),
+            Vec2::new(10.0 - 8.0 / 3.0, -3.0),
+        );
+    }
+
+    #[test]
+    fn screen_world_round_trip() {
+        let viewport = viewport();
+        let cameras = [OrthographicCamera::default(), moved_camera()];
+
+        for camera in &cameras {
+            for pixel in pixels() {
+                let world = camera.screen_to_world(pixel, &viewport);
+
+                assert_close(camera.world_to_screen(world.extend(0.0), &viewport), pixel);
+            }
+
+            for &point in &[Vec2::ZERO, Vec2::new(3.0, -7.5), Vec2::new(10.0, -5.0)] {
+                let pixel = camera.world_to_screen(point.extend(0.0), &viewport);
+
+                assert_close(camera.screen_to_world(pixel, &viewport), point);
+            }
+        }
+    }
+
+    #[test]
+    fn world_bounds_cover_the_view() {
+        let bounds = OrthographicCamera::default().world_bounds(4.0 / 3.0);
+
+        assert_close(bounds.min, Vec2::new(-4.0 / 3.0, -1.0));
+        assert_close(bounds.max, Vec2::new(4.0 / 3.0, 1.0));
+
+        let bounds = moved_camera().world_bounds(4.0 / 3.0);
+
+        assert_close(bounds.min, Vec2::new(10.0 - 8.0 / 3.0, -7.0));
+        assert_close(bounds.max, Vec2::new(10.0 + 8.0 / 3.0, -3.0));
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use glam::{Quat, Vec2, Vec3};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, Touch, TouchPhase, WindowEvent};
+
+use crate::{Camera, OrthographicCamera, Rect, Transform, Viewport};
+
+/// Editor style panning by dragging and zooming towards the cursor with the wheel or
+/// by pinching.
+///
+/// Zooming changes [`OrthographicCamera::size`], so the camera should use
+/// [`ScaleMode::Aspect`](crate::ScaleMode::Aspect).
+#[derive(Clone, Debug)]
+pub struct PanZoomController {
+    /// Smallest size the camera zooms in to.
+    pub min_size: f32,
+    /// Largest size the camera zooms out to.
+    pub max_size: f32,
+    /// Factor the size changes by per line scrolled.
+    pub zoom_speed: f32,
+    pub pan_button: MouseButton,
+    cursor: Option<Vec2>,
+    dragging: bool,
+    touches: HashMap<u64, Vec2>,
+}
+
+impl Default for PanZoomController {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            min_size: 0.01,
+            max_size: 1000.0,
+            zoom_speed: 1.1,
+            pan_button: MouseButton::Middle,
+            cursor: None,
+            dragging: false,
+            touches: HashMap::new(),
+        }
+    }
+}
+
+impl PanZoomController {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn zoom_limits(mut self, min_size: f32, max_size: f32) -> Self {
+        self.min_size = min_size;
+        self.max_size = max_size;
+        self
+    }
+
+    #[inline]
+    pub fn pan_button(mut self, button: MouseButton) -> Self {
+        self.pan_button = button;
+        self
+    }
+
+    /// Moves `camera` so the world point under pixel `from` ends up under `to`.
+    #[inline]
+    pub fn pan(&self, camera: &mut OrthographicCamera, from: Vec2, to: Vec2, viewport: &Viewport) {
+        let from = camera.screen_to_world(from, viewport);
+        let to = camera.screen_to_world(to, viewport);
+
+        camera.transform.translation += (from - to).extend(0.0);
+    }
+
+    /// Multiplies the size of `camera` by `factor`, within the zoom limits, keeping the
+    /// world point under `pixel` in place.
+    #[inline]
+    pub fn zoom_at(
+        &self,
+        camera: &mut OrthographicCamera,
+        pixel: Vec2,
+        factor: f32,
+        viewport: &Viewport,
+    ) {
+        let before = camera.screen_to_world(pixel, viewport);
+        camera.size = (camera.size * factor).max(self.min_size).min(self.max_size);
+        let after = camera.screen_to_world(pixel, viewport);
+
+        camera.transform.translation += (before - after).extend(0.0);
+    }
+
+    /// Updates `camera` from `event`, returns true if the event was used.
+    #[inline]
+    pub fn handle_event(
+        &mut self,
+        camera: &mut OrthographicCamera,
+        event: &WindowEvent<'_>,
+        viewport: &Viewport,
+    ) -> bool {
+        match *event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = Vec2::new(position.x as f32, position.y as f32);
+
+                if let (true, Some(cursor)) = (self.dragging, self.cursor) {
+                    self.pan(camera, cursor, position, viewport);
+                }
+
+                self.cursor = Some(position);
+                self.dragging
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                self.dragging = false;
+                false
+            }
+            WindowEvent::MouseInput { state, button, .. } if button == self.pan_button => {
+                self.dragging = state == ElementState::Pressed;
+                true
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    // roughly the height of a line
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 20.0,
+                };
+
+                let pixel = self.cursor.unwrap_or_else(|| viewport.rect.center());
+                self.zoom_at(camera, pixel, self.zoom_speed.powf(-lines), viewport);
+                true
+            }
+            WindowEvent::Touch(Touch {
+                phase,
+                location,
+                id,
+                ..
+            }) => {
+                let location = Vec2::new(location.x as f32, location.y as f32);
+
+                match phase {
+                    TouchPhase::Started => {
+                        self.touches.insert(id, location);
+                    }
+                    TouchPhase::Moved => self.touch_moved(camera, id, location, viewport),
+                    TouchPhase::Ended | TouchPhase::Cancelled => {
+                        self.touches.remove(&id);
+                    }
+                }
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Pans with a single finger, pans and zooms with two.
+    #[inline]
+    fn touch_moved(
+        &mut self,
+        camera: &mut OrthographicCamera,
+        id: u64,
+        location: Vec2,
+        viewport: &Viewport,
+    ) {
+        let previous = match self.touches.insert(id, location) {
+            Some(previous) => previous,
+            None => return,
+        };
+
+        match self.touches.len() {
+            1 => self.pan(camera, previous, location, viewport),
+            2 => {
+                let other = self
+                    .touches
+                    .iter()
+                    .find(|&(&other, _)| other != id)
+                    .map(|(_, &position)| position)
+                    .unwrap();
+
+                let old_center = (previous + other) / 2.0;
+                let new_center = (location + other) / 2.0;
+                let old_distance = previous.distance(other);
+                let new_distance = location.distance(other);
+
+                self.pan(camera, old_center, new_center, viewport);
+
+                if new_distance > 0.0 {
+                    self.zoom_at(camera, new_center, old_distance / new_distance, viewport);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Smoothly moves a camera towards a target.
+#[derive(Clone, Debug)]
+pub struct FollowController {
+    /// Seconds it takes to cover half the distance to the target.
+    pub half_life: f32,
+    /// Offset from the target in world space.
+    pub offset: Vec2,
+}
+
+impl Default for FollowController {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            half_life: 0.1,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl FollowController {
+    #[inline]
+    pub fn new(half_life: f32) -> Self {
+        Self {
+            half_life,
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Moves `camera` towards `target` for `delta_time` seconds, independently of the
+    /// frame rate.
+    #[inline]
+    pub fn update(&self, camera: &mut OrthographicCamera, target: &Transform, delta_time: f32) {
+        let goal = target.translation.truncate() + self.offset;
+        let current = camera.transform.translation.truncate();
+
+        let t = if self.half_life > 0.0 {
+            1.0 - 0.5f32.powf(delta_time / self.half_life)
+        } else {
+            1.0
+        };
+
+        let position = current.lerp(goal, t);
+        camera.transform.translation.x = position.x;
+        camera.transform.translation.y = position.y;
+    }
+}
+
+/// Keeps the visible area of a camera inside world bounds.
+#[derive(Clone, Debug)]
+pub struct CameraBounds {
+    pub bounds: Rect,
+}
+
+impl CameraBounds {
+    #[inline]
+    pub fn new(bounds: Rect) -> Self {
+        Self { bounds }
+    }
+
+    /// Moves `camera` so what it shows on a target of `aspect` stays inside the
+    /// bounds, centering it along axes where the bounds are smaller than the view.
+    #[inline]
+    pub fn apply(&self, camera: &mut OrthographicCamera, aspect: f32) {
+        let visible = camera.world_bounds(aspect);
+
+        let shift = |visible_min: f32, visible_max: f32, min: f32, max: f32| {
+            if visible_max - visible_min > max - min {
+                (min + max) / 2.0 - (visible_min + visible_max) / 2.0
+            } else if visible_min < min {
+                min - visible_min
+            } else if visible_max > max {
+                max - visible_max
+            } else {
+                0.0
+            }
+        };
+
+        camera.transform.translation.x += shift(
+            visible.min.x,
+            visible.max.x,
+            self.bounds.min.x,
+            self.bounds.max.x,
+        );
+        camera.transform.translation.y += shift(
+            visible.min.y,
+            visible.max.y,
+            self.bounds.min.y,
+            self.bounds.max.y,
+        );
+    }
+}
+
+/// Trauma based screen shake, shaking harder the more trauma is added and calming
+/// down over time.
+#[derive(Clone, Debug)]
+pub struct CameraShake {
+    /// Largest offset in world units.
+    pub max_offset: Vec2,
+    /// Largest rotation in radians.
+    pub max_angle: f32,
+    /// Trauma removed per second.
+    pub decay: f32,
+    /// How fast the shake moves.
+    pub frequency: f32,
+    trauma: f32,
+    time: f32,
+}
+
+impl Default for CameraShake {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_offset: Vec2::splat(0.1),
+            max_angle: 0.05,
+            decay: 1.0,
+            frequency: 15.0,
+            trauma: 0.0,
+            time: 0.0,
+        }
+    }
+}
+
+impl CameraShake {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `amount` of trauma, trauma is kept between 0 and 1.
+    #[inline]
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).max(0.0).min(1.0);
+    }
+
+    #[inline]
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    #[inline]
+    pub fn update(&mut self, delta_time: f32) {
+        self.trauma = (self.trauma - self.decay * delta_time).max(0.0);
+        self.time += delta_time;
+    }
+
+    /// A shaken copy of `camera` to draw with, leaving the camera itself in place.
+    #[inline]
+    pub fn apply(&self, camera: &OrthographicCamera) -> OrthographicCamera {
+        // squared so small amounts of trauma barely shake
+        let shake = self.trauma * self.trauma;
+        let t = self.time * self.frequency;
+
+        let mut camera = camera.clone();
+        camera.transform.translation += Vec3::new(
+            self.max_offset.x * shake * noise(t, 0.0),
+            self.max_offset.y * shake * noise(t, 1.0),
+            0.0,
+        );
+        camera.transform.rotation *= Quat::from_rotation_z(self.max_angle * shake * noise(t, 2.0));
+
+        camera
+    }
+}
+
+/// Smooth noise between -1 and 1, a different curve for every `seed`.
+#[inline]
+fn noise(t: f32, seed: f32) -> f32 {
+    let seed = seed * 12.9898;
+
+    ((t + seed).sin() + (t * 2.3 + seed * 1.7).sin() * 0.5 + (t * 5.1 + seed * 2.9).sin() * 0.25)
+        / 1.75
+}
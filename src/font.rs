@@ -0,0 +1,168 @@
+use std::{
+    borrow::Cow,
+    fmt,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use ab_glyph::{Font as _, FontArc};
+
+/// Ubuntu Light, see `fonts/UFL.txt` for the license.
+const DEFAULT_FONT: &[u8] = include_bytes!("fonts/Ubuntu-Light.ttf");
+
+/// Id 0 is reserved for the default font.
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A TrueType or OpenType font.
+///
+/// Fonts are cheap to clone, the font data is shared.
+///
+/// Characters the font has no glyph for are taken from the fallback fonts, in the
+/// order they were added, see [`Font::with_fallback`].
+#[derive(Clone)]
+pub struct Font {
+    id: u64,
+    /// Borrows `data`, declared first so it's dropped before it. Never clone it out of
+    /// the font.
+    font: FontArc,
+    /// Raw font data, used for shaping.
+    data: Arc<Cow<'static, [u8]>>,
+    /// Underline position and thickness in font units.
+    underline: Option<ttf_parser::LineMetrics>,
+    /// Strikethrough position and thickness in font units.
+    strikethrough: Option<ttf_parser::LineMetrics>,
+    fallbacks: Arc<Vec<Font>>,
+}
+
+impl Font {
+    #[inline]
+    pub fn from_bytes(data: Vec<u8>) -> anyhow::Result<Self> {
+        let data: Arc<Cow<'static, [u8]>> = Arc::new(Cow::Owned(data));
+
+        // SAFETY: the bytes live on the heap behind `data`, which is never mutated and
+        // is kept alive by every clone of the font next to `font`, which is dropped first
+        let bytes: &'static [u8] = unsafe { &*(&**data as *const [u8]) };
+        let font = FontArc::try_from_slice(bytes).map_err(|_| anyhow::anyhow!("invalid font"))?;
+
+        Ok(Self::with_data(
+            NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+            font,
+            data,
+        ))
+    }
+
+    #[inline]
+    fn with_data(id: u64, font: FontArc, data: Arc<Cow<'static, [u8]>>) -> Self {
+        let face = ttf_parser::Face::from_slice(&data, 0).ok();
+
+        Self {
+            id,
+            font,
+            underline: face.as_ref().and_then(|face| face.underline_metrics()),
+            strikethrough: face.as_ref().and_then(|face| face.strikeout_metrics()),
+            data,
+            fallbacks: Arc::new(Vec::new()),
+        }
+    }
+
+    #[inline]
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Unique id of the font, used to cache glyphs.
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Adds a font to take glyphs from when this font doesn't have them, like emoji or
+    /// CJK characters.
+    #[inline]
+    pub fn with_fallback(mut self, fallback: Font) -> Self {
+        Arc::make_mut(&mut self.fallbacks).push(fallback);
+        self
+    }
+
+    #[inline]
+    pub fn fallbacks(&self) -> &[Font] {
+        &self.fallbacks
+    }
+
+    /// This font followed by its fallbacks and theirs, depth first.
+    #[inline]
+    pub fn chain(&self) -> Vec<&Font> {
+        let mut chain = vec![self];
+
+        for fallback in self.fallbacks.iter() {
+            chain.extend(fallback.chain());
+        }
+
+        chain
+    }
+
+    /// Top and thickness of underlines at `size`, y pointing up from the baseline.
+    #[inline]
+    pub(crate) fn underline(&self, size: f32) -> (f32, f32) {
+        self.line_metrics(size, self.underline)
+            .unwrap_or((-0.1 * size, 0.05 * size))
+    }
+
+    /// Top and thickness of strikethroughs at `size`, y pointing up from the baseline.
+    #[inline]
+    pub(crate) fn strikethrough(&self, size: f32) -> (f32, f32) {
+        self.line_metrics(size, self.strikethrough)
+            .unwrap_or((0.3 * size, 0.05 * size))
+    }
+
+    #[inline]
+    fn line_metrics(
+        &self,
+        size: f32,
+        metrics: Option<ttf_parser::LineMetrics>,
+    ) -> Option<(f32, f32)> {
+        // same scale as the glyphs, ab_glyph scales to ascent minus descent
+        let scale = size / self.font.height_unscaled();
+        let metrics = metrics?;
+
+        Some((
+            metrics.position as f32 * scale,
+            metrics.thickness as f32 * scale,
+        ))
+    }
+
+    #[inline]
+    pub(crate) fn inner(&self) -> &FontArc {
+        &self.font
+    }
+
+    #[inline]
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Default for Font {
+    /// The font bundled with paper.
+    #[inline]
+    fn default() -> Self {
+        Self::with_data(
+            0,
+            FontArc::try_from_slice(DEFAULT_FONT).unwrap(),
+            Arc::new(Cow::Borrowed(DEFAULT_FONT)),
+        )
+    }
+}
+
+impl fmt::Debug for Font {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Font")
+            .field("id", &self.id)
+            .field("fallbacks", &self.fallbacks)
+            .finish()
+    }
+}
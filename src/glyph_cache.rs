@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use ab_glyph::{point, Font as _, Glyph, GlyphId, PxScale};
+use glam::Vec2;
+
+use crate::{AtlasRegion, Font, RenderInstance, TextureAtlas};
+
+/// Number of horizontal subpixel positions glyphs are rasterized at.
+const SUBPIXEL_STEPS: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: u64,
+    glyph: u16,
+    /// Bits of the pixel size.
+    size: u32,
+    subpixel: u8,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GlyphEntry {
+    glyph: Option<CachedGlyph>,
+    last_used: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CachedGlyph {
+    pub region: AtlasRegion,
+    /// Offset of the top left corner from the pen position in pixels, y pointing down.
+    pub offset: Vec2,
+    pub size: Vec2,
+}
+
+/// Rasterized glyphs packed into a [`TextureAtlas`].
+///
+/// Glyphs not drawn for a while are evicted by [`GlyphCache::end_frame`], so text drawn
+/// at ever changing sizes, like while zooming, doesn't grow the cache without bound.
+#[derive(Debug, Default)]
+pub struct GlyphCache {
+    atlas: TextureAtlas,
+    /// Glyphs without an outline, like spaces, are cached as [`None`].
+    glyphs: HashMap<GlyphKey, GlyphEntry>,
+    frame_index: u64,
+}
+
+impl GlyphCache {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn atlas(&self) -> &TextureAtlas {
+        &self.atlas
+    }
+
+    /// Splits a horizontal pen position in pixels into the whole pixel the glyph is drawn
+    /// at and the subpixel offset it is rasterized with.
+    #[inline]
+    pub fn snap(x: f32) -> (f32, f32) {
+        let whole = x.floor();
+        let subpixel = ((x - whole) * SUBPIXEL_STEPS).floor() / SUBPIXEL_STEPS;
+
+        (whole, subpixel)
+    }
+
+    /// Gets or rasterizes `glyph` at `px_size` pixels, offset by `subpixel` pixels
+    /// horizontally.
+    #[inline]
+    pub fn glyph(
+        &mut self,
+        instance: &RenderInstance,
+        font: &Font,
+        glyph: GlyphId,
+        px_size: f32,
+        subpixel: f32,
+    ) -> Option<CachedGlyph> {
+        let key = GlyphKey {
+            font: font.id(),
+            glyph: glyph.0,
+            size: px_size.to_bits(),
+            subpixel: (subpixel * SUBPIXEL_STEPS) as u8,
+        };
+
+        let frame_index = self.frame_index;
+
+        if let Some(entry) = self.glyphs.get_mut(&key) {
+            entry.last_used = frame_index;
+            return entry.glyph;
+        }
+
+        let cached = Self::rasterize(&mut self.atlas, instance, font, glyph, px_size, subpixel);
+
+        self.glyphs.insert(
+            key,
+            GlyphEntry {
+                glyph: cached,
+                last_used: frame_index,
+            },
+        );
+
+        cached
+    }
+
+    /// Evicts glyphs that haven't been drawn in `trim_frames` frames.
+    ///
+    /// The atlas can't free single regions, once less than half of it holds cached
+    /// glyphs it's cleared and the glyphs still in use are rasterized again when next
+    /// drawn. Call it after the frame was submitted, the regions of the frame are
+    /// invalid afterwards.
+    #[inline]
+    pub fn end_frame(&mut self, trim_frames: u32) {
+        let frame_index = self.frame_index;
+
+        self.glyphs
+            .retain(|_, entry| frame_index - entry.last_used < trim_frames as u64);
+
+        let cached = self
+            .glyphs
+            .values()
+            .filter(|entry| entry.glyph.is_some())
+            .count();
+
+        if cached * 2 < self.atlas.len() {
+            self.atlas.clear();
+            self.glyphs.clear();
+        }
+
+        self.frame_index += 1;
+    }
+
+    #[inline]
+    fn rasterize(
+        atlas: &mut TextureAtlas,
+        instance: &RenderInstance,
+        font: &Font,
+        glyph: GlyphId,
+        px_size: f32,
+        subpixel: f32,
+    ) -> Option<CachedGlyph> {
+        let outlined = font.inner().outline_glyph(Glyph {
+            id: glyph,
+            scale: PxScale::from(px_size),
+            position: point(subpixel, 0.0),
+        })?;
+
+        let bounds = outlined.px_bounds();
+        let width = bounds.width() as u32;
+        let height = bounds.height() as u32;
+
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        // white glyphs with coverage in alpha, colored by the sprite tint
+        let mut data = [255, 255, 255, 0].repeat(width as usize * height as usize);
+
+        outlined.draw(|x, y, coverage| {
+            let i = (y * width + x) as usize * 4;
+            data[i + 3] = (coverage.min(1.0) * 255.0) as u8;
+        });
+
+        let region = atlas.add(instance, width, height, &data);
+
+        Some(CachedGlyph {
+            region,
+            offset: Vec2::new(bounds.min.x, bounds.min.y),
+            size: Vec2::new(width as f32, height as f32),
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    primary_pipeline::{depth_stencil_state, VERTEX_LAYOUT},
+    BlendMode, RenderInstance,
+};
+
+/// Size of the gradient uniforms of a single draw.
+pub const GRADIENT_UNIFORMS_SIZE: u64 = 544;
+
+/// Layout of the per draw gradient uniforms.
+pub fn gradient_uniforms(instance: &RenderInstance) -> wgpu::BindGroupLayout {
+    instance
+        .device
+        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("gradient uniforms"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(GRADIENT_UNIFORMS_SIZE),
+                },
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                count: None,
+            }],
+        })
+}
+
+pub fn gradient_pipeline(
+    instance: &RenderInstance,
+    format: wgpu::TextureFormat,
+    uniforms: &wgpu::BindGroupLayout,
+    gradient: &wgpu::BindGroupLayout,
+    blend_mode: BlendMode,
+) -> wgpu::RenderPipeline {
+    let module = instance
+        .device
+        .create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("gradient shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                blend_mode
+                    .shader_source(include_str!("shaders/gradient.wgsl"))
+                    .into(),
+            ),
+            flags: wgpu::ShaderFlags::all(),
+        });
+
+    let layout = instance
+        .device
+        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("gradient layout"),
+            bind_group_layouts: &[uniforms, gradient],
+            push_constant_ranges: &[],
+        });
+
+    instance
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("gradient pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                buffers: &[VERTEX_LAYOUT],
+                entry_point: "main",
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: blend_mode.blend_state(),
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+                entry_point: "main",
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: wgpu::MultisampleState {
+                count: 8,
+                ..Default::default()
+            },
+            depth_stencil: Some(depth_stencil_state()),
+        })
+}
@@ -0,0 +1,170 @@
+use glam::{Mat4, Vec2, Vec4};
+
+use crate::Viewport;
+
+/// Where the ray through `pixel` of `viewport` hits the local plane z = 0 of
+/// `matrix`, the transform from local to clip space.
+#[inline]
+pub(crate) fn pixel_to_local(matrix: Mat4, pixel: Vec2, viewport: &Viewport) -> Option<Vec2> {
+    let inverse = matrix.inverse();
+    let ndc = viewport.pixel_to_ndc(pixel);
+
+    let unproject = |z: f32| {
+        let point = inverse * Vec4::new(ndc.x, ndc.y, z, 1.0);
+        point.truncate() / point.w
+    };
+
+    let near = unproject(0.0);
+    let far = unproject(1.0);
+    let direction = far - near;
+
+    if direction.z.abs() < f32::EPSILON {
+        // looking along the plane, fall back to the point under the pixel
+        return Some(near.truncate());
+    }
+
+    let t = -near.z / direction.z;
+
+    if !(0.0..=1.0).contains(&t) {
+        return None;
+    }
+
+    Some((near + direction * t).truncate())
+}
+
+/// Whether `point` lies on any triangle of a mesh, in the xy plane. `vertices` are mesh
+/// vertices, position followed by color.
+#[inline]
+pub(crate) fn mesh_contains(vertices: &[[f32; 7]], indices: &[u32], point: Vec2) -> bool {
+    let position = |index: u32| {
+        let vertex = vertices[index as usize];
+        Vec2::new(vertex[0], vertex[1])
+    };
+
+    indices.chunks_exact(3).any(|triangle| {
+        triangle_contains(
+            position(triangle[0]),
+            position(triangle[1]),
+            position(triangle[2]),
+            point,
+        )
+    })
+}
+
+/// Whether `point` lies on the triangle `a`, `b`, `c` of either winding.
+#[inline]
+fn triangle_contains(a: Vec2, b: Vec2, c: Vec2, point: Vec2) -> bool {
+    let cross = |a: Vec2, b: Vec2, p: Vec2| (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x);
+
+    let ab = cross(a, b, point);
+    let bc = cross(b, c, point);
+    let ca = cross(c, a, point);
+
+    (ab >= 0.0 && bc >= 0.0 && ca >= 0.0) || (ab <= 0.0 && bc <= 0.0 && ca <= 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Camera, OrthographicCamera, Rect};
+
+    /// A right triangle with its corner at the origin.
+    fn triangle() -> (Vec2, Vec2, Vec2) {
+        (Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(0.0, 2.0))
+    }
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn triangle_contains_either_winding() {
+        let (a, b, c) = triangle();
+        let inside = Vec2::new(0.5, 0.5);
+        let outside = Vec2::new(1.5, 1.5);
+
+        assert!(triangle_contains(a, b, c, inside));
+        assert!(triangle_contains(a, c, b, inside));
+        assert!(!triangle_contains(a, b, c, outside));
+        assert!(!triangle_contains(a, c, b, outside));
+    }
+
+    #[test]
+    fn triangle_contains_edges_and_corners() {
+        let (a, b, c) = triangle();
+
+        for &point in &[
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            a,
+            b,
+            c,
+        ] {
+            assert!(triangle_contains(a, b, c, point), "{}", point);
+            assert!(triangle_contains(a, c, b, point), "{}", point);
+        }
+
+        // on the line through an edge but past the corner
+        assert!(!triangle_contains(a, b, c, Vec2::new(3.0, 0.0)));
+    }
+
+    #[test]
+    fn mesh_contains_any_triangle() {
+        let vertex = |x: f32, y: f32| [x, y, 0.0, 1.0, 1.0, 1.0, 1.0];
+
+        // a unit square split into two triangles and a separate triangle beside it
+        let vertices = [
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(1.0, 1.0),
+            vertex(0.0, 1.0),
+            vertex(2.0, 0.0),
+            vertex(3.0, 0.0),
+            vertex(2.0, 1.0),
+        ];
+        let indices = [0, 1, 2, 0, 2, 3, 4, 5, 6];
+
+        assert!(mesh_contains(&vertices, &indices, Vec2::new(0.75, 0.25)));
+        assert!(mesh_contains(&vertices, &indices, Vec2::new(0.25, 0.75)));
+        assert!(mesh_contains(&vertices, &indices, Vec2::new(2.25, 0.25)));
+        assert!(!mesh_contains(&vertices, &indices, Vec2::new(1.5, 0.5)));
+        assert!(!mesh_contains(&vertices, &indices, Vec2::new(2.75, 0.75)));
+
+        // vertices without triangles cover nothing
+        assert!(!mesh_contains(&vertices, &[], Vec2::new(0.5, 0.5)));
+    }
+
+    #[test]
+    fn pixel_to_local_orthographic_with_offset_viewport() {
+        let viewport =
+            Viewport::from_rect(Rect::new(Vec2::new(100.0, 50.0), Vec2::new(300.0, 250.0)));
+        let camera = OrthographicCamera::default();
+        let view_proj = camera.view_proj(viewport.aspect());
+
+        let local = |transform: Mat4, pixel: Vec2| {
+            pixel_to_local(view_proj * transform, pixel, &viewport).unwrap()
+        };
+
+        assert_close(local(Mat4::IDENTITY, Vec2::new(200.0, 150.0)), Vec2::ZERO);
+        // y points down in pixels and up in world space
+        assert_close(
+            local(Mat4::IDENTITY, Vec2::new(300.0, 50.0)),
+            Vec2::new(1.0, 1.0),
+        );
+        assert_close(
+            local(Mat4::IDENTITY, Vec2::new(100.0, 250.0)),
+            Vec2::new(-1.0, -1.0),
+        );
+
+        let transform = Mat4::from_scale_rotation_translation(
+            glam::Vec3::new(2.0, 2.0, 1.0),
+            glam::Quat::IDENTITY,
+            glam::Vec3::new(0.5, 0.0, 0.0),
+        );
+        assert_close(
+            local(transform, Vec2::new(200.0, 150.0)),
+            Vec2::new(-0.25, 0.0),
+        );
+    }
+}
@@ -0,0 +1,12 @@
+/// Renderables are drawn in order of their layer, higher layers on top of lower ones.
+///
+/// Layers can be hidden with [`Renderer::set_layer_visible`](crate::Renderer::set_layer_visible),
+/// or for a single frame with [`Frame::set_layer_visible`](crate::Frame::set_layer_visible).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Layer(pub i32);
+
+impl Layer {
+    pub const BACKGROUND: Self = Self(-100);
+    pub const DEFAULT: Self = Self(0);
+    pub const UI: Self = Self(100);
+}
@@ -1,12 +1,57 @@
 mod app;
+mod atlas;
+mod blend;
+mod bounds;
+mod camera;
+mod camera_controller;
+mod font;
+mod glyph_cache;
+mod gradient_pipeline;
+mod hit_test;
+mod layer;
+mod mask;
+mod mask_pipeline;
+mod material;
+mod mesh_handle;
+mod paint;
+mod post;
 mod primary_pipeline;
+mod rect;
 mod render;
+mod render_graph;
 mod renderer;
+mod sprite;
+mod sprite_pipeline;
+mod stats;
+mod text;
+mod text_layout;
+mod texture;
 mod transform;
+mod upload;
 
 pub use app::*;
+pub use atlas::*;
+pub use blend::*;
+pub use bounds::*;
+pub use camera::*;
+pub use camera_controller::*;
+pub use font::*;
 pub use glam::{swizzles::*, *};
+pub use glyph_cache::*;
+pub use layer::*;
+pub use mask::*;
+pub use material::*;
+pub use mesh_handle::*;
+pub use paint::*;
+pub use post::*;
+pub use rect::*;
 pub use render::*;
+pub use render_graph::*;
 pub use renderer::*;
 pub use scissor::*;
+pub use sprite::*;
+pub use stats::*;
+pub use text_layout::*;
+pub use texture::*;
 pub use transform::*;
+pub(crate) use upload::*;
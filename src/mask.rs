@@ -0,0 +1,16 @@
+/// Which side of a mask shape following draws are kept on, see
+/// [`Frame::push_mask`](crate::Frame::push_mask).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MaskMode {
+    /// Keep what's inside the shape.
+    Inside,
+    /// Keep what's outside the shape.
+    Outside,
+}
+
+impl Default for MaskMode {
+    #[inline]
+    fn default() -> Self {
+        Self::Inside
+    }
+}
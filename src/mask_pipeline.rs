@@ -0,0 +1,78 @@
+use crate::{
+    primary_pipeline::{DEPTH_FORMAT, VERTEX_LAYOUT},
+    BlendMode, RenderInstance,
+};
+
+/// Pipeline drawing a mask shape into the stencil buffer, applying `operation` to pixels
+/// whose mask level equals the stencil reference. Color and depth are left untouched.
+pub fn mask_pipeline(
+    instance: &RenderInstance,
+    format: wgpu::TextureFormat,
+    uniforms: &wgpu::BindGroupLayout,
+    operation: wgpu::StencilOperation,
+) -> wgpu::RenderPipeline {
+    let module = instance
+        .device
+        .create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("mask shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                BlendMode::Normal
+                    .shader_source(include_str!("shaders/primary.wgsl"))
+                    .into(),
+            ),
+            flags: wgpu::ShaderFlags::all(),
+        });
+
+    let layout = instance
+        .device
+        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("mask layout"),
+            bind_group_layouts: &[uniforms],
+            push_constant_ranges: &[],
+        });
+
+    let face = wgpu::StencilFaceState {
+        compare: wgpu::CompareFunction::Equal,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op: operation,
+    };
+
+    instance
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("mask pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                buffers: &[VERTEX_LAYOUT],
+                entry_point: "main",
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrite::empty(),
+                }],
+                entry_point: "main",
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: wgpu::MultisampleState {
+                count: 8,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState {
+                    front: face,
+                    back: face,
+                    read_mask: 0xff,
+                    write_mask: 0xff,
+                },
+                bias: wgpu::DepthBiasState::default(),
+            }),
+        })
+}
@@ -0,0 +1,317 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bytemuck::Pod;
+use futures::executor::block_on;
+
+use crate::{
+    primary_pipeline::{depth_stencil_state, VERTEX_LAYOUT},
+    BlendMode, RenderInstance, Sampler, Texture,
+};
+
+static NEXT_MATERIAL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A user supplied WGSL shader with its uniforms and textures, drawn with
+/// [`Frame::draw_shape_with_material`](crate::Frame::draw_shape_with_material).
+///
+/// # Shader contract
+/// Both stages use `main` as entry point and the fragment stage writes a single color
+/// to location 0, which is blended with the blend mode of the draw.
+///
+/// The fragment stage returns its color through `output(color: vec4<f32>) -> vec4<f32>`,
+/// which paper prepends to the shader. It takes the color with straight alpha, or
+/// premultiplied with [`BlendMode::Premultiplied`], and premultiplies it for the blend
+/// modes that expect it, since the shader is compiled for every blend mode it's drawn
+/// with.
+///
+/// Vertex inputs:
+/// - `[[location(0)]] position: vec3<f32>`
+/// - `[[location(1)]] color: vec4<f32>`
+///
+/// Bind groups:
+/// - `[[group(0), binding(0)]]` paper uniforms, `transform: mat4x4<f32>` followed by
+///   `view_proj: mat4x4<f32>`.
+/// - `[[group(1), binding(0)]]` the material uniform block, if
+///   [`MaterialBuilder::uniforms`] was set.
+/// - `[[group(1), binding(1 + 2 * i)]]` the `i`th texture as `texture_2d<f32>` and
+///   `[[group(1), binding(2 + 2 * i)]]` its sampler.
+#[derive(Debug)]
+pub struct Material {
+    id: u64,
+    /// Shader source without `output`, compiled for every blend mode.
+    source: String,
+    layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    uniforms: Option<wgpu::Buffer>,
+    uniforms_size: u64,
+}
+
+impl Material {
+    #[inline]
+    pub fn builder<'a>(source: impl Into<String>) -> MaterialBuilder<'a> {
+        MaterialBuilder {
+            source: source.into(),
+            uniforms: None,
+            textures: Vec::new(),
+        }
+    }
+
+    /// Unique id of the material, used to cache pipelines.
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Replaces the uniform block, `uniforms` must be the same type the material was
+    /// built with.
+    #[inline]
+    pub fn set_uniforms<T: Pod>(&self, instance: &RenderInstance, uniforms: &T) {
+        let buffer = self
+            .uniforms
+            .as_ref()
+            .expect("material was built without uniforms");
+        let data = bytemuck::bytes_of(uniforms);
+
+        assert_eq!(
+            data.len() as u64,
+            self.uniforms_size,
+            "uniforms must be the type the material was built with"
+        );
+
+        instance.queue.write_buffer(buffer, 0, &padded(data));
+    }
+
+    #[inline]
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    #[inline]
+    pub(crate) fn pipeline(
+        &self,
+        instance: &RenderInstance,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        blend_mode: BlendMode,
+        uniforms: &wgpu::BindGroupLayout,
+    ) -> wgpu::RenderPipeline {
+        let module = instance
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some("material shader"),
+                source: wgpu::ShaderSource::Wgsl(blend_mode.shader_source(&self.source).into()),
+                flags: wgpu::ShaderFlags::all(),
+            });
+
+        let layout = instance
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("material layout"),
+                bind_group_layouts: &[uniforms, &self.layout],
+                push_constant_ranges: &[],
+            });
+
+        instance
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("material pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    buffers: &[VERTEX_LAYOUT],
+                    entry_point: "main",
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    targets: &[wgpu::ColorTargetState {
+                        format,
+                        blend: blend_mode.blend_state(),
+                        write_mask: wgpu::ColorWrite::ALL,
+                    }],
+                    entry_point: "main",
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                depth_stencil: Some(depth_stencil_state()),
+            })
+    }
+}
+
+/// Builds a [`Material`], see [`Material::builder`].
+pub struct MaterialBuilder<'a> {
+    source: String,
+    uniforms: Option<Vec<u8>>,
+    textures: Vec<(&'a Texture, Sampler)>,
+}
+
+impl<'a> MaterialBuilder<'a> {
+    /// Sets the initial uniform block, update it with [`Material::set_uniforms`].
+    ///
+    /// # Panics
+    /// Panics if `T` is zero sized, leave the uniforms unset instead.
+    #[inline]
+    pub fn uniforms<T: Pod>(mut self, uniforms: &T) -> Self {
+        assert!(
+            std::mem::size_of::<T>() > 0,
+            "material uniforms can't be zero sized"
+        );
+
+        self.uniforms = Some(bytemuck::bytes_of(uniforms).to_vec());
+        self
+    }
+
+    /// Adds a texture, bound after the previously added ones.
+    #[inline]
+    pub fn texture(mut self, texture: &'a Texture, sampler: Sampler) -> Self {
+        self.textures.push((texture, sampler));
+        self
+    }
+
+    /// Compiles the shader, failing if it isn't valid WGSL.
+    ///
+    /// Whether the shader matches the [shader contract](Material#shader-contract) is
+    /// only checked when it's first drawn, mismatches panic then.
+    #[inline]
+    pub fn build(self, instance: &RenderInstance) -> anyhow::Result<Material> {
+        // catch shader errors instead of the device panicking on them
+        instance
+            .device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+
+        // only the prepended `output` differs between blend modes
+        instance
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some("material shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    BlendMode::Normal.shader_source(&self.source).into(),
+                ),
+                flags: wgpu::ShaderFlags::all(),
+            });
+
+        if let Some(error) = block_on(instance.device.pop_error_scope()) {
+            return Err(anyhow::anyhow!("invalid material shader: {}", error));
+        }
+
+        let mut layout_entries = Vec::new();
+
+        if let Some(ref uniforms) = self.uniforms {
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(uniform_buffer_size(uniforms)),
+                },
+                visibility: wgpu::ShaderStage::VERTEX_FRAGMENT,
+                count: None,
+            });
+        }
+
+        for i in 0..self.textures.len() as u32 {
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 1 + 2 * i,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                visibility: wgpu::ShaderStage::VERTEX_FRAGMENT,
+                count: None,
+            });
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 2 + 2 * i,
+                ty: wgpu::BindingType::Sampler {
+                    filtering: true,
+                    comparison: false,
+                },
+                visibility: wgpu::ShaderStage::VERTEX_FRAGMENT,
+                count: None,
+            });
+        }
+
+        let layout = instance
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("material bindings"),
+                entries: &layout_entries,
+            });
+
+        let uniforms = self.uniforms.as_ref().map(|uniforms| {
+            let buffer = instance.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("material uniforms"),
+                size: uniform_buffer_size(uniforms),
+                usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            instance.queue.write_buffer(&buffer, 0, &padded(uniforms));
+
+            buffer
+        });
+
+        let samplers: Vec<_> = self
+            .textures
+            .iter()
+            .map(|(_, sampler)| sampler.create(instance))
+            .collect();
+
+        let mut entries = Vec::new();
+
+        if let Some(ref buffer) = uniforms {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            });
+        }
+
+        for (i, ((texture, _), sampler)) in self.textures.iter().zip(&samplers).enumerate() {
+            let i = i as u32;
+
+            entries.push(wgpu::BindGroupEntry {
+                binding: 1 + 2 * i,
+                resource: wgpu::BindingResource::TextureView(texture.view()),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2 + 2 * i,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            });
+        }
+
+        let bind_group = instance
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("material bindings"),
+                layout: &layout,
+                entries: &entries,
+            });
+
+        Ok(Material {
+            id: NEXT_MATERIAL_ID.fetch_add(1, Ordering::Relaxed),
+            source: self.source,
+            layout,
+            bind_group,
+            uniforms_size: self
+                .uniforms
+                .as_ref()
+                .map_or(0, |uniforms| uniforms.len() as u64),
+            uniforms,
+        })
+    }
+}
+
+/// Uniform blocks are padded to a multiple of 16 bytes in WGSL.
+#[inline]
+fn uniform_buffer_size(uniforms: &[u8]) -> u64 {
+    (uniforms.len() as u64 + 15) / 16 * 16
+}
+
+/// `uniforms` padded to the size of their buffer, writes must be a multiple of 4 bytes.
+#[inline]
+fn padded(uniforms: &[u8]) -> Vec<u8> {
+    let mut data = uniforms.to_vec();
+    data.resize(uniform_buffer_size(uniforms) as usize, 0);
+    data
+}
@@ -0,0 +1,124 @@
+use bytemuck::cast_slice;
+use scissor::mesh::Mesh;
+use wgpu::util::DeviceExt;
+
+use crate::{Aabb, RenderInstance};
+
+/// A [`Mesh`] uploaded to the gpu.
+///
+/// The buffers are released when the handle is dropped.
+#[derive(Debug)]
+pub struct MeshHandle {
+    pub(crate) vertex_buffer: wgpu::Buffer,
+    pub(crate) vertex_buffer_size: usize,
+    pub(crate) index_buffer: wgpu::Buffer,
+    pub(crate) index_buffer_size: usize,
+    pub(crate) vertices: u32,
+    pub(crate) indices: u32,
+    /// Copy of the vertices and indices kept for hit testing, see
+    /// [`RenderInstance::upload_mesh_hit_testable`].
+    pub(crate) hit_test_data: Option<(Vec<[f32; 7]>, Vec<u32>)>,
+    /// Bounds of the mesh, computed on upload, draws outside the view are culled.
+    /// `None` never culls.
+    pub bounds: Option<Aabb>,
+}
+
+impl MeshHandle {
+    #[inline]
+    pub fn indices(&self) -> u32 {
+        self.indices
+    }
+
+    /// Whether a copy of the mesh is kept for hit testing.
+    #[inline]
+    pub fn hit_testable(&self) -> bool {
+        self.hit_test_data.is_some()
+    }
+}
+
+impl RenderInstance {
+    #[inline]
+    pub fn upload_mesh(&self, mesh: &Mesh) -> MeshHandle {
+        let vertex_data: &[u8] = cast_slice(&mesh.vertices);
+        let index_data: &[u8] = cast_slice(&mesh.indices);
+
+        MeshHandle {
+            vertex_buffer: self.create_vertex_buffer(vertex_data),
+            vertex_buffer_size: vertex_data.len(),
+            index_buffer: self.create_index_buffer(index_data),
+            index_buffer_size: index_data.len(),
+            vertices: mesh.vertices.len() as u32,
+            indices: mesh.indices.len() as u32,
+            hit_test_data: None,
+            bounds: Aabb::from_mesh(mesh),
+        }
+    }
+
+    /// Uploads `mesh` and keeps a copy of it on the cpu, so draws of it are hit tested
+    /// triangle by triangle with [`Frame::hit_test`](crate::Frame::hit_test) instead of
+    /// by their bounds.
+    #[inline]
+    pub fn upload_mesh_hit_testable(&self, mesh: &Mesh) -> MeshHandle {
+        let mut handle = self.upload_mesh(mesh);
+        handle.hit_test_data = Some(hit_test_data(mesh));
+        handle
+    }
+
+    /// Replaces the contents of `handle` with `mesh`, reusing the existing buffers
+    /// when they are large enough. The copy of hit testable handles is updated too.
+    #[inline]
+    pub fn update_mesh(&self, handle: &mut MeshHandle, mesh: &Mesh) {
+        let vertex_data: &[u8] = cast_slice(&mesh.vertices);
+
+        if vertex_data.len() <= handle.vertex_buffer_size {
+            self.queue
+                .write_buffer(&handle.vertex_buffer, 0, vertex_data);
+        } else {
+            handle.vertex_buffer = self.create_vertex_buffer(vertex_data);
+            handle.vertex_buffer_size = vertex_data.len();
+        }
+
+        let index_data: &[u8] = cast_slice(&mesh.indices);
+
+        if index_data.len() <= handle.index_buffer_size {
+            self.queue.write_buffer(&handle.index_buffer, 0, index_data);
+        } else {
+            handle.index_buffer = self.create_index_buffer(index_data);
+            handle.index_buffer_size = index_data.len();
+        }
+
+        handle.vertices = mesh.vertices.len() as u32;
+        handle.indices = mesh.indices.len() as u32;
+
+        if handle.hit_test_data.is_some() {
+            handle.hit_test_data = Some(hit_test_data(mesh));
+        }
+
+        handle.bounds = Aabb::from_mesh(mesh);
+    }
+
+    #[inline]
+    fn create_vertex_buffer(&self, data: &[u8]) -> wgpu::Buffer {
+        self.device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("mesh vertex buffer"),
+                contents: data,
+                usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::VERTEX,
+            })
+    }
+
+    #[inline]
+    fn create_index_buffer(&self, data: &[u8]) -> wgpu::Buffer {
+        self.device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("mesh index buffer"),
+                contents: data,
+                usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::INDEX,
+            })
+    }
+}
+
+#[inline]
+fn hit_test_data(mesh: &Mesh) -> (Vec<[f32; 7]>, Vec<u32>) {
+    (cast_slice(&mesh.vertices).to_vec(), mesh.indices.clone())
+}
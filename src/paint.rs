@@ -0,0 +1,158 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+
+/// Maximum number of color stops of a [`Gradient`], later stops are ignored.
+pub const MAX_GRADIENT_STOPS: usize = 16;
+
+/// How a [`Gradient`] continues past its first and last stop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpreadMode {
+    /// Extends the colors of the first and last stop.
+    Pad,
+    /// Starts over from the first stop.
+    Repeat,
+    /// Mirrors the gradient every other time.
+    Reflect,
+}
+
+impl Default for SpreadMode {
+    #[inline]
+    fn default() -> Self {
+        Self::Pad
+    }
+}
+
+/// The geometry of a [`Gradient`], in the local space of the shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientKind {
+    /// Varies along the line from `start` to `end`.
+    Linear { start: Vec2, end: Vec2 },
+    /// Varies with the distance from `center`, reaching the last stop at `radius`.
+    Radial { center: Vec2, radius: f32 },
+    /// Varies with the angle around `center`, counter clockwise from `angle` in
+    /// radians.
+    Conic { center: Vec2, angle: f32 },
+}
+
+/// A color at `offset` along a [`Gradient`], from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    pub offset: f32,
+    pub color: [f32; 4],
+}
+
+/// A smooth transition between colors, evaluated per pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<ColorStop>,
+    pub spread: SpreadMode,
+}
+
+impl Gradient {
+    #[inline]
+    pub fn new(kind: GradientKind) -> Self {
+        Self {
+            kind,
+            stops: Vec::new(),
+            spread: SpreadMode::Pad,
+        }
+    }
+
+    #[inline]
+    pub fn linear(start: Vec2, end: Vec2) -> Self {
+        Self::new(GradientKind::Linear { start, end })
+    }
+
+    #[inline]
+    pub fn radial(center: Vec2, radius: f32) -> Self {
+        Self::new(GradientKind::Radial { center, radius })
+    }
+
+    #[inline]
+    pub fn conic(center: Vec2, angle: f32) -> Self {
+        Self::new(GradientKind::Conic { center, angle })
+    }
+
+    /// Adds a color stop, stops don't have to be added in order.
+    #[inline]
+    pub fn stop(mut self, offset: f32, color: [f32; 4]) -> Self {
+        self.stops.push(ColorStop { offset, color });
+        self
+    }
+
+    #[inline]
+    pub fn spread(mut self, spread: SpreadMode) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn uniforms(&self) -> GradientUniforms {
+        let mut stops = self.stops.clone();
+        stops.sort_by(|a, b| {
+            a.offset
+                .partial_cmp(&b.offset)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        stops.truncate(MAX_GRADIENT_STOPS);
+
+        let (kind, geometry) = match self.kind {
+            GradientKind::Linear { start, end } => (0, [start.x, start.y, end.x, end.y]),
+            GradientKind::Radial { center, radius } => (1, [center.x, center.y, radius, 0.0]),
+            GradientKind::Conic { center, angle } => (2, [center.x, center.y, angle, 0.0]),
+        };
+
+        let mut uniforms = GradientUniforms {
+            kind,
+            spread: self.spread as u32,
+            count: stops.len() as u32,
+            _padding: 0,
+            geometry,
+            offsets: [[0.0; 4]; MAX_GRADIENT_STOPS],
+            colors: [[0.0; 4]; MAX_GRADIENT_STOPS],
+        };
+
+        for (i, stop) in stops.iter().enumerate() {
+            uniforms.offsets[i][0] = stop.offset;
+            uniforms.colors[i] = stop.color;
+        }
+
+        uniforms
+    }
+}
+
+/// How a shape is filled.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    Solid([f32; 4]),
+    Gradient(Gradient),
+}
+
+impl From<[f32; 4]> for Paint {
+    #[inline]
+    fn from(color: [f32; 4]) -> Self {
+        Self::Solid(color)
+    }
+}
+
+impl From<Gradient> for Paint {
+    #[inline]
+    fn from(gradient: Gradient) -> Self {
+        Self::Gradient(gradient)
+    }
+}
+
+/// Gradient uniform block of the gradient shader.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub(crate) struct GradientUniforms {
+    kind: u32,
+    spread: u32,
+    count: u32,
+    _padding: u32,
+    geometry: [f32; 4],
+    /// Offset of each stop in x, padded to the array stride of uniform blocks.
+    offsets: [[f32; 4]; MAX_GRADIENT_STOPS],
+    colors: [[f32; 4]; MAX_GRADIENT_STOPS],
+}
@@ -0,0 +1,481 @@
+use bytemuck::{Pod, Zeroable};
+use futures::executor::block_on;
+
+use crate::{RenderInstance, Texture};
+
+/// A full-screen pass running a user supplied WGSL fragment shader.
+///
+/// # Shader contract
+/// The fragment stage uses `main` as entry point, takes the uv of the pixel as
+/// `[[location(0)]] uv: vec2<f32>`, y pointing down, and writes a color to location 0.
+///
+/// Bindings of group 0:
+/// - `binding(0)` the output of the previous pass as `texture_2d<f32>`.
+/// - `binding(1)` a linear clamping sampler.
+/// - `binding(2)` the parameter uniform block.
+/// - `binding(3)` the input of the effect, the output of the previous effect, as
+///   `texture_2d<f32>`.
+/// - `binding(4 + i)` the `i`th texture added with [`PostPass::texture`].
+#[derive(Debug)]
+pub struct PostPass {
+    fragment: wgpu::ShaderModule,
+    params: wgpu::Buffer,
+    params_size: u64,
+    textures: Vec<Texture>,
+    pipeline: Option<PostPipeline>,
+}
+
+#[derive(Debug)]
+struct PostPipeline {
+    format: wgpu::TextureFormat,
+    layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl PostPass {
+    /// Creates a pass without parameters, see [`PostPass::with_params`].
+    #[inline]
+    pub fn new(instance: &RenderInstance, source: impl Into<String>) -> anyhow::Result<Self> {
+        Self::with_params(instance, source, &[0u32; 4])
+    }
+
+    /// Compiles the shader, failing if it isn't valid WGSL.
+    ///
+    /// Whether the shader matches the [shader contract](PostPass#shader-contract) is only
+    /// checked when it's first run, mismatches panic then.
+    #[inline]
+    pub fn with_params<T: Pod>(
+        instance: &RenderInstance,
+        source: impl Into<String>,
+        params: &T,
+    ) -> anyhow::Result<Self> {
+        let source: String = source.into();
+
+        // catch shader errors instead of the device panicking on them
+        instance
+            .device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let fragment = instance
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some("post shader"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+                flags: wgpu::ShaderFlags::all(),
+            });
+
+        if let Some(error) = block_on(instance.device.pop_error_scope()) {
+            return Err(anyhow::anyhow!("invalid post shader: {}", error));
+        }
+
+        let data = bytemuck::bytes_of(params);
+        // uniform blocks are padded to a multiple of 16 bytes in WGSL
+        let size = (data.len() as u64 + 15) / 16 * 16;
+
+        let buffer = instance.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("post params"),
+            size,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        instance.queue.write_buffer(&buffer, 0, data);
+
+        Ok(Self {
+            fragment,
+            params: buffer,
+            params_size: data.len() as u64,
+            textures: Vec::new(),
+            pipeline: None,
+        })
+    }
+
+    /// A pass running one of the shaders bundled with paper.
+    #[inline]
+    fn builtin<T: Pod>(instance: &RenderInstance, source: &str, params: &T) -> Self {
+        Self::with_params(instance, source, params).expect("builtin post shaders are valid")
+    }
+
+    /// Adds a texture, bound after the previously added ones.
+    #[inline]
+    pub fn texture(mut self, texture: Texture) -> Self {
+        self.textures.push(texture);
+        self.pipeline = None;
+        self
+    }
+
+    /// Replaces the parameters, `params` must be the type the pass was created with.
+    #[inline]
+    pub fn set_params<T: Pod>(&self, instance: &RenderInstance, params: &T) {
+        let data = bytemuck::bytes_of(params);
+
+        assert_eq!(
+            data.len() as u64,
+            self.params_size,
+            "params must be the type the pass was created with"
+        );
+
+        instance.queue.write_buffer(&self.params, 0, data);
+    }
+
+    /// Creates the pipeline rendering to `format` if it doesn't exist yet.
+    #[inline]
+    fn prepare(&mut self, instance: &RenderInstance, format: wgpu::TextureFormat) {
+        if let Some(ref pipeline) = self.pipeline {
+            if pipeline.format == format {
+                return;
+            }
+        }
+
+        let vertex = instance
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some("fullscreen shader"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("shaders/post/fullscreen.wgsl").into(),
+                ),
+                flags: wgpu::ShaderFlags::all(),
+            });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            visibility: wgpu::ShaderStage::FRAGMENT,
+            count: None,
+        };
+
+        let mut entries = vec![
+            texture_entry(0),
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                ty: wgpu::BindingType::Sampler {
+                    filtering: true,
+                    comparison: false,
+                },
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                count: None,
+            },
+            texture_entry(3),
+        ];
+
+        for i in 0..self.textures.len() as u32 {
+            entries.push(texture_entry(4 + i));
+        }
+
+        let layout = instance
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("post bindings"),
+                entries: &entries,
+            });
+
+        let pipeline_layout =
+            instance
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("post layout"),
+                    bind_group_layouts: &[&layout],
+                    push_constant_ranges: &[],
+                });
+
+        let pipeline = instance
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("post pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &vertex,
+                    buffers: &[],
+                    entry_point: "main",
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.fragment,
+                    targets: &[wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrite::ALL,
+                    }],
+                    entry_point: "main",
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                multisample: wgpu::MultisampleState::default(),
+                depth_stencil: None,
+            });
+
+        self.pipeline = Some(PostPipeline {
+            format,
+            layout,
+            pipeline,
+        });
+    }
+}
+
+/// A full-screen effect applied after the primary pass, made of one or more passes.
+///
+/// Effects are added to a [`Renderer`](crate::Renderer) with
+/// [`Renderer::push_effect`](crate::Renderer::push_effect) and run in order.
+#[derive(Debug)]
+pub struct PostEffect {
+    pub passes: Vec<PostPass>,
+    pub enabled: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct BlurParams {
+    direction: [f32; 2],
+    radius: f32,
+    _padding: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct BloomParams {
+    threshold: f32,
+    intensity: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct VignetteParams {
+    strength: f32,
+    radius: f32,
+    softness: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ColorGradingParams {
+    size: f32,
+    intensity: f32,
+}
+
+impl PostEffect {
+    #[inline]
+    pub fn new(passes: Vec<PostPass>) -> Self {
+        Self {
+            passes,
+            enabled: true,
+        }
+    }
+
+    /// A single pass effect, see [`PostPass`] for the shader contract.
+    #[inline]
+    pub fn custom(pass: PostPass) -> Self {
+        Self::new(vec![pass])
+    }
+
+    #[inline]
+    fn blur_passes(instance: &RenderInstance, radius: f32) -> Vec<PostPass> {
+        let pass = |direction| {
+            PostPass::builtin(
+                instance,
+                include_str!("shaders/post/blur.wgsl"),
+                &BlurParams {
+                    direction,
+                    radius,
+                    _padding: 0.0,
+                },
+            )
+        };
+
+        vec![pass([1.0, 0.0]), pass([0.0, 1.0])]
+    }
+
+    /// Gaussian blur, `radius` scales the spread of the samples in pixels.
+    #[inline]
+    pub fn blur(instance: &RenderInstance, radius: f32) -> Self {
+        Self::new(Self::blur_passes(instance, radius))
+    }
+
+    /// Adds a blurred copy of the parts brighter than `threshold` on top.
+    #[inline]
+    pub fn bloom(instance: &RenderInstance, threshold: f32, intensity: f32, radius: f32) -> Self {
+        let params = BloomParams {
+            threshold,
+            intensity,
+        };
+
+        let mut passes = vec![PostPass::builtin(
+            instance,
+            include_str!("shaders/post/bloom_threshold.wgsl"),
+            &params,
+        )];
+        passes.extend(Self::blur_passes(instance, radius));
+        passes.push(PostPass::builtin(
+            instance,
+            include_str!("shaders/post/bloom_composite.wgsl"),
+            &params,
+        ));
+
+        Self::new(passes)
+    }
+
+    /// Darkens the edges by up to `strength`, starting at `radius` from the center in
+    /// uv units and reaching full strength `softness` further out.
+    #[inline]
+    pub fn vignette(instance: &RenderInstance, strength: f32, radius: f32, softness: f32) -> Self {
+        Self::custom(PostPass::builtin(
+            instance,
+            include_str!("shaders/post/vignette.wgsl"),
+            &VignetteParams {
+                strength,
+                radius,
+                softness,
+            },
+        ))
+    }
+
+    /// Maps colors through a lookup table, blended with the original by `intensity`.
+    ///
+    /// `lut` is a strip of `size` slices of `size` by `size` pixels side by side, one
+    /// slice per blue value, with red along x and green along y. Both the lookup and the
+    /// colors in it are in srgb, like lookup tables are usually authored, so an identity
+    /// table loaded with [`Texture::open`] leaves the colors unchanged.
+    #[inline]
+    pub fn color_grading(instance: &RenderInstance, lut: Texture, intensity: f32) -> Self {
+        let size = lut.height() as f32;
+
+        Self::custom(
+            PostPass::builtin(
+                instance,
+                include_str!("shaders/post/color_grading.wgsl"),
+                &ColorGradingParams { size, intensity },
+            )
+            .texture(lut),
+        )
+    }
+
+    /// Copies the input unchanged.
+    #[inline]
+    pub(crate) fn copy(instance: &RenderInstance) -> Self {
+        Self::custom(PostPass::builtin(
+            instance,
+            include_str!("shaders/post/copy.wgsl"),
+            &[0u32; 4],
+        ))
+    }
+
+    /// Splits the red and blue channels towards the edges by up to `amount` pixels.
+    #[inline]
+    pub fn chromatic_aberration(instance: &RenderInstance, amount: f32) -> Self {
+        Self::custom(PostPass::builtin(
+            instance,
+            include_str!("shaders/post/chromatic_aberration.wgsl"),
+            &amount,
+        ))
+    }
+}
+
+/// Runs the enabled `effects` on the scene in `targets[0]`, writing the result to
+/// `output`.
+///
+/// `targets` are three single sampled textures of `format`, an effect reads its input
+/// from one and ping-pongs between the other two.
+#[inline]
+pub(crate) fn apply_effects(
+    instance: &RenderInstance,
+    encoder: &mut wgpu::CommandEncoder,
+    effects: &mut [PostEffect],
+    targets: [&wgpu::TextureView; 3],
+    sampler: &wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    output: &wgpu::TextureView,
+) {
+    let pass_count: usize = effects
+        .iter()
+        .filter(|effect| effect.enabled)
+        .map(|effect| effect.passes.len())
+        .sum();
+
+    let mut input = 0;
+    let mut pass_index = 0;
+
+    for effect in effects.iter_mut().filter(|effect| effect.enabled) {
+        let free = [(input + 1) % 3, (input + 2) % 3];
+        let mut source = input;
+
+        for (i, pass) in effect.passes.iter_mut().enumerate() {
+            pass_index += 1;
+
+            let target = free[i % 2];
+            let view = if pass_index == pass_count {
+                output
+            } else {
+                targets[target]
+            };
+
+            pass.prepare(instance, format);
+            let pipeline = pass.pipeline.as_ref().unwrap();
+
+            let mut entries = vec![
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(targets[source]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: pass.params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(targets[input]),
+                },
+            ];
+
+            for (i, texture) in pass.textures.iter().enumerate() {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 4 + i as u32,
+                    resource: wgpu::BindingResource::TextureView(texture.view()),
+                });
+            }
+
+            let bind_group = instance
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("post bindings"),
+                    layout: &pipeline.layout,
+                    entries: &entries,
+                });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("post pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            render_pass.set_pipeline(&pipeline.pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+
+            source = target;
+        }
+
+        input = source;
+    }
+}
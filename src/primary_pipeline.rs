@@ -1,18 +1,54 @@
-use crate::RenderInstance;
+use crate::{BlendMode, RenderInstance};
 
-pub fn primary_pipeline(
-    instance: &RenderInstance,
-    format: wgpu::TextureFormat,
-) -> (wgpu::RenderPipeline, wgpu::BindGroupLayout) {
-    let module = instance
-        .device
-        .create_shader_module(&wgpu::ShaderModuleDescriptor {
-            label: Some("primary shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/primary.wgsl").into()),
-            flags: wgpu::ShaderFlags::all(),
-        });
+/// Format of the depth buffer of the primary pass, the stencil holds the mask level.
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+/// Vertex buffer layout of meshes, a position followed by a color, shared by the
+/// pipelines drawing them.
+pub(crate) const VERTEX_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+    array_stride: 12 + 16,
+    step_mode: wgpu::InputStepMode::Vertex,
+    attributes: &[
+        wgpu::VertexAttribute {
+            offset: 0,
+            format: wgpu::VertexFormat::Float32x3,
+            shader_location: 0,
+        },
+        wgpu::VertexAttribute {
+            offset: 12,
+            format: wgpu::VertexFormat::Float32x4,
+            shader_location: 1,
+        },
+    ],
+};
+
+/// Depth and stencil state of pipelines drawing content, only pixels whose mask level
+/// equals the stencil reference are drawn.
+pub fn depth_stencil_state() -> wgpu::DepthStencilState {
+    let face = wgpu::StencilFaceState {
+        compare: wgpu::CompareFunction::Equal,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op: wgpu::StencilOperation::Keep,
+    };
 
-    let uniforms = instance
+    wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: true,
+        depth_compare: wgpu::CompareFunction::LessEqual,
+        stencil: wgpu::StencilState {
+            front: face,
+            back: face,
+            read_mask: 0xff,
+            write_mask: 0,
+        },
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
+
+/// Layout of the per draw uniforms shared by all pipelines.
+pub fn primary_uniforms(instance: &RenderInstance) -> wgpu::BindGroupLayout {
+    instance
         .device
         .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("primary uniforms"),
@@ -20,52 +56,56 @@ pub fn primary_pipeline(
                 binding: 0,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(128),
                 },
                 visibility: wgpu::ShaderStage::VERTEX_FRAGMENT,
                 count: None,
             }],
+        })
+}
+
+pub fn primary_pipeline(
+    instance: &RenderInstance,
+    format: wgpu::TextureFormat,
+    uniforms: &wgpu::BindGroupLayout,
+    blend_mode: BlendMode,
+) -> wgpu::RenderPipeline {
+    let module = instance
+        .device
+        .create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("primary shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                blend_mode
+                    .shader_source(include_str!("shaders/primary.wgsl"))
+                    .into(),
+            ),
+            flags: wgpu::ShaderFlags::all(),
         });
 
     let layout = instance
         .device
         .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("primary layout"),
-            bind_group_layouts: &[&uniforms],
+            bind_group_layouts: &[uniforms],
             push_constant_ranges: &[],
         });
 
-    let pipeline = instance
+    instance
         .device
         .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("primary pipeline"),
             layout: Some(&layout),
             vertex: wgpu::VertexState {
                 module: &module,
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: 12 + 16,
-                    step_mode: wgpu::InputStepMode::Vertex,
-                    attributes: &[
-                        wgpu::VertexAttribute {
-                            offset: 0,
-                            format: wgpu::VertexFormat::Float32x3,
-                            shader_location: 0,
-                        },
-                        wgpu::VertexAttribute {
-                            offset: 12,
-                            format: wgpu::VertexFormat::Float32x4,
-                            shader_location: 1,
-                        },
-                    ],
-                }],
+                buffers: &[VERTEX_LAYOUT],
                 entry_point: "main",
             },
             fragment: Some(wgpu::FragmentState {
                 module: &module,
                 targets: &[wgpu::ColorTargetState {
                     format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    blend: blend_mode.blend_state(),
                     write_mask: wgpu::ColorWrite::ALL,
                 }],
                 entry_point: "main",
@@ -75,14 +115,6 @@ pub fn primary_pipeline(
                 count: 8,
                 ..Default::default()
             },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth24Plus,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-        });
-
-    (pipeline, uniforms)
+            depth_stencil: Some(depth_stencil_state()),
+        })
 }
@@ -0,0 +1,927 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::RenderInstance;
+
+/// Size of a texture allocated by a [`RenderGraph`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextureSize {
+    /// The size of the render target.
+    Target,
+    /// The size of the render target scaled by a factor, `0.5` for half resolution.
+    Scaled(f32),
+    /// A fixed size in pixels.
+    Fixed(u32, u32),
+}
+
+impl TextureSize {
+    #[inline]
+    fn pixels(self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Self::Target => (width, height),
+            Self::Scaled(scale) => (
+                ((width as f32 * scale) as u32).max(1),
+                ((height as f32 * scale) as u32).max(1),
+            ),
+            Self::Fixed(width, height) => (width.max(1), height.max(1)),
+        }
+    }
+}
+
+/// A transient texture created by a node, see [`NodeBuilder::create_texture`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureDesc {
+    /// Format of the texture, the format of the render target if `None`.
+    pub format: Option<wgpu::TextureFormat>,
+    pub size: TextureSize,
+    pub sample_count: u32,
+}
+
+impl Default for TextureDesc {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            format: None,
+            size: TextureSize::Target,
+            sample_count: 1,
+        }
+    }
+}
+
+impl TextureDesc {
+    #[inline]
+    pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    #[inline]
+    pub fn size(mut self, size: TextureSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    #[inline]
+    pub fn sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+}
+
+/// A transient buffer created by a node, see [`NodeBuilder::create_buffer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferDesc {
+    pub size: u64,
+    pub usage: wgpu::BufferUsage,
+}
+
+/// Resources a node reads, writes and creates, see [`RenderNode::setup`].
+#[derive(Debug, Default)]
+pub struct NodeBuilder {
+    reads: Vec<String>,
+    writes: Vec<String>,
+    textures: Vec<(String, TextureDesc)>,
+    buffers: Vec<(String, BufferDesc)>,
+}
+
+impl NodeBuilder {
+    /// The node reads `name`, it runs after the nodes writing it.
+    #[inline]
+    pub fn read(&mut self, name: impl Into<String>) -> &mut Self {
+        self.reads.push(name.into());
+        self
+    }
+
+    /// The node writes `name`, it runs after the nodes added before it that write or
+    /// read it.
+    #[inline]
+    pub fn write(&mut self, name: impl Into<String>) -> &mut Self {
+        self.writes.push(name.into());
+        self
+    }
+
+    /// The node creates and writes the texture `name`, which lives until its last
+    /// reader has run.
+    #[inline]
+    pub fn create_texture(&mut self, name: impl Into<String>, desc: TextureDesc) -> &mut Self {
+        let name = name.into();
+
+        self.textures.push((name.clone(), desc));
+        self.write(name)
+    }
+
+    /// The node creates and writes the buffer `name`, which lives until its last reader
+    /// has run.
+    #[inline]
+    pub fn create_buffer(&mut self, name: impl Into<String>, desc: BufferDesc) -> &mut Self {
+        let name = name.into();
+
+        self.buffers.push((name.clone(), desc));
+        self.write(name)
+    }
+
+    #[inline]
+    fn uses(&self, name: &str) -> bool {
+        self.reads
+            .iter()
+            .chain(&self.writes)
+            .any(|used| used == name)
+    }
+}
+
+/// A pass of a [`RenderGraph`].
+pub trait RenderNode {
+    /// Declares the resources of the node, called every frame before the graph is
+    /// ordered.
+    fn setup(&mut self, builder: &mut NodeBuilder);
+
+    /// Records the commands of the node.
+    fn run(&mut self, context: &mut NodeContext<'_>);
+}
+
+/// What a [`RenderNode`] records its commands with.
+pub struct NodeContext<'a> {
+    pub instance: &'a RenderInstance,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    resources: &'a GraphResources,
+    output: &'a wgpu::TextureView,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+}
+
+impl<'a> NodeContext<'a> {
+    /// View of the texture `name` for sampling, multisampled textures are resolved.
+    ///
+    /// `None` if no node creates `name` this frame.
+    #[inline]
+    pub fn texture(&self, name: &str) -> Option<&'a wgpu::TextureView> {
+        let resources: &'a GraphResources = self.resources;
+        resources.texture(name, self.output)
+    }
+
+    /// Color attachment rendering into the texture `name`, resolving it if it's
+    /// multisampled and read by another node.
+    ///
+    /// `None` if no node creates `name` this frame.
+    #[inline]
+    pub fn color_attachment(
+        &self,
+        name: &str,
+        ops: wgpu::Operations<wgpu::Color>,
+    ) -> Option<wgpu::RenderPassColorAttachment<'a>> {
+        let resources: &'a GraphResources = self.resources;
+        let (view, resolve_target) = resources.attachment(name, self.output)?;
+
+        Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops,
+        })
+    }
+
+    /// The buffer `name`, `None` if no node creates it this frame.
+    #[inline]
+    pub fn buffer(&self, name: &str) -> Option<&'a wgpu::Buffer> {
+        let resources: &'a GraphResources = self.resources;
+        resources.buffer(name)
+    }
+
+    /// Format of the render target.
+    #[inline]
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+/// Nodes implemented by the renderer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BuiltinNode {
+    /// Draws the renderables of the frame into [`RenderGraph::SCENE`].
+    Primary,
+    /// Runs the post-processing effects, reading [`RenderGraph::SCENE`] and writing
+    /// [`RenderGraph::OUTPUT`].
+    Post,
+}
+
+enum Node {
+    Builtin(BuiltinNode),
+    Custom(Box<dyn RenderNode>),
+}
+
+struct GraphNode {
+    name: String,
+    node: Node,
+    builder: NodeBuilder,
+}
+
+/// Passes rendering a frame, ordered by the resources they read and write.
+///
+/// The graph starts with the [`RenderGraph::PRIMARY`] node drawing the frame into
+/// [`RenderGraph::SCENE`] and the [`RenderGraph::POST`] node running post-processing
+/// into [`RenderGraph::OUTPUT`]. Nodes added after them run after them when they use
+/// the same resources, so a node writing [`RenderGraph::OUTPUT`] draws over the
+/// finished frame.
+///
+/// Textures and buffers created by nodes are transient, they only live for the frame
+/// and are reused by later nodes once their last reader has run.
+pub struct RenderGraph {
+    nodes: Vec<GraphNode>,
+    resources: GraphResources,
+    /// Node and resource names of missing resources already logged, so they're logged
+    /// once instead of every frame.
+    reported: HashSet<(String, String)>,
+}
+
+impl std::fmt::Debug for RenderGraph {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderGraph")
+            .field("nodes", &self.node_names().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Default for RenderGraph {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderGraph {
+    /// The node drawing the frame.
+    pub const PRIMARY: &'static str = "primary";
+    /// The node running post-processing effects.
+    pub const POST: &'static str = "post";
+    /// The render target, the swapchain.
+    pub const OUTPUT: &'static str = "output";
+    /// The multisampled color the frame is drawn into.
+    pub const SCENE: &'static str = "scene";
+    /// The depth and stencil buffer of the frame.
+    pub const DEPTH: &'static str = "depth";
+
+    #[inline]
+    pub fn new() -> Self {
+        let mut graph = Self {
+            nodes: Vec::new(),
+            resources: GraphResources::default(),
+            reported: HashSet::new(),
+        };
+
+        graph.push(Self::PRIMARY, Node::Builtin(BuiltinNode::Primary));
+        graph.push(Self::POST, Node::Builtin(BuiltinNode::Post));
+
+        graph
+    }
+
+    /// Adds `node` after the existing ones, replacing any node called `name`.
+    #[inline]
+    pub fn add_node(&mut self, name: impl Into<String>, node: impl RenderNode + 'static) {
+        let name = name.into();
+
+        self.remove_node(&name);
+        self.push(name, Node::Custom(Box::new(node)));
+    }
+
+    /// Removes the node called `name`, returns false if there is none.
+    #[inline]
+    pub fn remove_node(&mut self, name: &str) -> bool {
+        let len = self.nodes.len();
+        self.nodes.retain(|node| node.name != name);
+        self.nodes.len() != len
+    }
+
+    #[inline]
+    pub fn contains_node(&self, name: &str) -> bool {
+        self.nodes.iter().any(|node| node.name == name)
+    }
+
+    /// Names of the nodes in the order they were added.
+    #[inline]
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|node| node.name.as_str())
+    }
+
+    #[inline]
+    fn push(&mut self, name: impl Into<String>, node: Node) {
+        self.nodes.push(GraphNode {
+            name: name.into(),
+            node,
+            builder: NodeBuilder::default(),
+        });
+    }
+
+    /// Sets up the nodes, with `builtin` declaring the resources of the builtin ones,
+    /// and allocates their resources. Returns the indices of the nodes in the order
+    /// they run.
+    #[inline]
+    pub(crate) fn prepare(
+        &mut self,
+        instance: &RenderInstance,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        mut builtin: impl FnMut(BuiltinNode, &mut NodeBuilder),
+    ) -> Vec<usize> {
+        for node in &mut self.nodes {
+            node.builder = NodeBuilder::default();
+
+            match node.node {
+                Node::Builtin(kind) => builtin(kind, &mut node.builder),
+                Node::Custom(ref mut custom) => custom.setup(&mut node.builder),
+            }
+        }
+
+        let builders: Vec<_> = self.nodes.iter().map(|node| &node.builder).collect();
+
+        for (i, name) in missing_resources(&builders) {
+            let node = &self.nodes[i].name;
+
+            if self.reported.insert((node.clone(), name.to_owned())) {
+                log::error!(
+                    "render graph node '{}' uses '{}', which no node creates",
+                    node,
+                    name
+                );
+            }
+        }
+
+        let order = order(&builders);
+        self.resources
+            .allocate(instance, format, width, height, &self.nodes, &order);
+
+        order
+    }
+
+    /// The builtin node at `index`, `None` for custom nodes.
+    #[inline]
+    pub(crate) fn builtin(&self, index: usize) -> Option<BuiltinNode> {
+        match self.nodes[index].node {
+            Node::Builtin(kind) => Some(kind),
+            Node::Custom(_) => None,
+        }
+    }
+
+    /// Whether any node reads `name` this frame.
+    #[inline]
+    pub(crate) fn is_read(&self, name: &str) -> bool {
+        self.nodes
+            .iter()
+            .any(|node| node.builder.reads.iter().any(|read| read == name))
+    }
+
+    /// The texture `name` created by a builtin node.
+    #[inline]
+    pub(crate) fn texture<'a>(
+        &'a self,
+        name: &str,
+        output: &'a wgpu::TextureView,
+    ) -> &'a wgpu::TextureView {
+        self.resources
+            .texture(name, output)
+            .expect("builtin nodes create their textures")
+    }
+
+    /// The texture `name` created by a builtin node and its resolve target.
+    #[inline]
+    pub(crate) fn attachment<'a>(
+        &'a self,
+        name: &str,
+        output: &'a wgpu::TextureView,
+    ) -> (&'a wgpu::TextureView, Option<&'a wgpu::TextureView>) {
+        self.resources
+            .attachment(name, output)
+            .expect("builtin nodes create their textures")
+    }
+
+    /// Runs the custom node at `index`.
+    #[inline]
+    pub(crate) fn run_custom(
+        &mut self,
+        index: usize,
+        instance: &RenderInstance,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) {
+        if let Node::Custom(ref mut node) = self.nodes[index].node {
+            node.run(&mut NodeContext {
+                instance,
+                encoder,
+                resources: &self.resources,
+                output,
+                format,
+                width,
+                height,
+            });
+        }
+    }
+}
+
+/// Orders nodes declaring `builders` so every node runs after the ones it depends on,
+/// nodes that don't depend on each other run in the order they were added.
+#[inline]
+fn order(builders: &[&NodeBuilder]) -> Vec<usize> {
+    let mut dependencies = vec![Vec::new(); builders.len()];
+
+    for (i, builder) in builders.iter().enumerate() {
+        for read in &builder.reads {
+            let writes = |j: &usize| builders[*j].writes.contains(read);
+
+            // reads the last version written before it, or what later nodes write
+            match (0..i).rev().find(writes) {
+                Some(writer) => dependencies[i].push(writer),
+                None => dependencies[i].extend((i + 1..builders.len()).filter(writes)),
+            }
+        }
+
+        for write in &builder.writes {
+            dependencies[i].extend((0..i).filter(|&j| {
+                builders[j].writes.contains(write)
+                    || (builders[j].reads.contains(write)
+                        && (0..j).any(|k| builders[k].writes.contains(write)))
+            }));
+        }
+    }
+
+    let mut order = Vec::with_capacity(builders.len());
+    let mut done = vec![false; builders.len()];
+
+    while order.len() < builders.len() {
+        let next =
+            (0..builders.len()).find(|&i| !done[i] && dependencies[i].iter().all(|&j| done[j]));
+
+        let next = match next {
+            Some(next) => next,
+            None => {
+                log::error!("render graph has a cycle, running the rest in added order");
+
+                (0..builders.len()).find(|&i| !done[i]).unwrap()
+            }
+        };
+
+        done[next] = true;
+        order.push(next);
+    }
+
+    order
+}
+
+/// Names nodes use without any node creating them, with the index of the node.
+#[inline]
+fn missing_resources<'a>(builders: &[&'a NodeBuilder]) -> Vec<(usize, &'a str)> {
+    let created = |name: &str| {
+        name == RenderGraph::OUTPUT
+            || builders.iter().any(|builder| {
+                builder
+                    .textures
+                    .iter()
+                    .map(|(created, _)| created)
+                    .chain(builder.buffers.iter().map(|(created, _)| created))
+                    .any(|created| created == name)
+            })
+    };
+
+    let mut missing = Vec::new();
+
+    for (i, builder) in builders.iter().enumerate() {
+        for name in builder.reads.iter().chain(&builder.writes) {
+            if !created(name.as_str()) && !missing.contains(&(i, name.as_str())) {
+                missing.push((i, name.as_str()));
+            }
+        }
+    }
+
+    missing
+}
+
+/// Position in `order` of the last node using `name`, 0 if none does.
+#[inline]
+fn last_use(builders: &[&NodeBuilder], order: &[usize], name: &str) -> usize {
+    order
+        .iter()
+        .rposition(|&i| builders[i].uses(name))
+        .unwrap_or(0)
+}
+
+/// Key of a pooled texture, format, size and sample count.
+type TextureKey = (wgpu::TextureFormat, u32, u32, u32);
+
+/// Key of a pooled buffer.
+type BufferKey = (u64, wgpu::BufferUsage);
+
+/// A pooled resource, shared by resources whose lifetimes don't overlap.
+struct Pooled<K, T> {
+    key: K,
+    value: T,
+    /// Position of the last node using it this frame.
+    busy_until: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+enum Allocation {
+    Texture {
+        view: usize,
+        /// Single sampled copy of a multisampled texture that is read.
+        resolve: Option<usize>,
+    },
+    Buffer(usize),
+}
+
+#[derive(Default)]
+struct GraphResources {
+    textures: Vec<Pooled<TextureKey, wgpu::TextureView>>,
+    buffers: Vec<Pooled<BufferKey, wgpu::Buffer>>,
+    allocations: HashMap<String, Allocation>,
+}
+
+impl GraphResources {
+    /// Allocates the resources created by `nodes`, run in `order`, reusing the ones of
+    /// the previous frame.
+    #[inline]
+    fn allocate(
+        &mut self,
+        instance: &RenderInstance,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        nodes: &[GraphNode],
+        order: &[usize],
+    ) {
+        // drop what wasn't used last frame
+        self.textures.retain(|texture| texture.busy_until.is_some());
+        self.buffers.retain(|buffer| buffer.busy_until.is_some());
+
+        for texture in &mut self.textures {
+            texture.busy_until = None;
+        }
+
+        for buffer in &mut self.buffers {
+            buffer.busy_until = None;
+        }
+
+        self.allocations.clear();
+
+        let builders: Vec<_> = nodes.iter().map(|node| &node.builder).collect();
+
+        for (position, &i) in order.iter().enumerate() {
+            let builder = &nodes[i].builder;
+
+            for (name, desc) in &builder.textures {
+                let (texture_width, texture_height) = desc.size.pixels(width, height);
+                let format = desc.format.unwrap_or(format);
+                let last = last_use(&builders, order, name);
+
+                let view = reserve(
+                    &mut self.textures,
+                    (format, texture_width, texture_height, desc.sample_count),
+                    position,
+                    last,
+                    |&(format, width, height, sample_count)| {
+                        create_texture(instance, format, width, height, sample_count)
+                    },
+                );
+
+                let read = nodes.iter().any(|node| node.builder.reads.contains(name));
+
+                let resolve = if desc.sample_count > 1 && read {
+                    Some(reserve(
+                        &mut self.textures,
+                        (format, texture_width, texture_height, 1),
+                        position,
+                        last,
+                        |&(format, width, height, sample_count)| {
+                            create_texture(instance, format, width, height, sample_count)
+                        },
+                    ))
+                } else {
+                    None
+                };
+
+                self.allocations
+                    .insert(name.clone(), Allocation::Texture { view, resolve });
+            }
+
+            for (name, desc) in &builder.buffers {
+                let buffer = reserve(
+                    &mut self.buffers,
+                    (desc.size, desc.usage),
+                    position,
+                    last_use(&builders, order, name),
+                    |&(size, usage)| {
+                        instance.device.create_buffer(&wgpu::BufferDescriptor {
+                            label: Some("render graph buffer"),
+                            size,
+                            usage,
+                            mapped_at_creation: false,
+                        })
+                    },
+                );
+
+                self.allocations
+                    .insert(name.clone(), Allocation::Buffer(buffer));
+            }
+        }
+    }
+
+    #[inline]
+    fn texture<'a>(
+        &'a self,
+        name: &str,
+        output: &'a wgpu::TextureView,
+    ) -> Option<&'a wgpu::TextureView> {
+        match self.attachment(name, output)? {
+            (_, Some(resolve)) => Some(resolve),
+            (view, None) => Some(view),
+        }
+    }
+
+    #[inline]
+    fn attachment<'a>(
+        &'a self,
+        name: &str,
+        output: &'a wgpu::TextureView,
+    ) -> Option<(&'a wgpu::TextureView, Option<&'a wgpu::TextureView>)> {
+        if name == RenderGraph::OUTPUT {
+            return Some((output, None));
+        }
+
+        match self.allocations.get(name) {
+            Some(&Allocation::Texture { view, resolve }) => Some((
+                &self.textures[view].value,
+                resolve.map(|resolve| &self.textures[resolve].value),
+            )),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn buffer(&self, name: &str) -> Option<&wgpu::Buffer> {
+        match self.allocations.get(name) {
+            Some(&Allocation::Buffer(buffer)) => Some(&self.buffers[buffer].value),
+            _ => None,
+        }
+    }
+}
+
+/// Finds a pooled resource matching `key` that's free from `first` on, or creates one,
+/// and reserves it until `last`.
+#[inline]
+fn reserve<K: PartialEq, T>(
+    pool: &mut Vec<Pooled<K, T>>,
+    key: K,
+    first: usize,
+    last: usize,
+    create: impl FnOnce(&K) -> T,
+) -> usize {
+    let free = pool.iter().position(|pooled| {
+        pooled.key == key && pooled.busy_until.map_or(true, |busy| busy < first)
+    });
+
+    let index = match free {
+        Some(index) => index,
+        None => {
+            let value = create(&key);
+
+            pool.push(Pooled {
+                key,
+                value,
+                busy_until: None,
+            });
+
+            pool.len() - 1
+        }
+    };
+
+    pool[index].busy_until = Some(last.max(first));
+
+    index
+}
+
+#[inline]
+fn create_texture(
+    instance: &RenderInstance,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    let texture = instance.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("render graph texture"),
+        format,
+        dimension: wgpu::TextureDimension::D2,
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        sample_count,
+        mip_level_count: 1,
+        usage: if sample_count > 1 {
+            wgpu::TextureUsage::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED
+        },
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(setup: impl FnOnce(&mut NodeBuilder)) -> NodeBuilder {
+        let mut builder = NodeBuilder::default();
+        setup(&mut builder);
+        builder
+    }
+
+    fn order_of(nodes: &[NodeBuilder]) -> Vec<usize> {
+        let builders: Vec<_> = nodes.iter().collect();
+        order(&builders)
+    }
+
+    #[test]
+    fn independent_nodes_keep_added_order() {
+        let nodes = [
+            node(|node| {
+                node.create_texture("a", TextureDesc::default());
+            }),
+            node(|node| {
+                node.create_texture("b", TextureDesc::default());
+            }),
+            node(|node| {
+                node.write(RenderGraph::OUTPUT);
+            }),
+        ];
+
+        assert_eq!(order_of(&nodes), [0, 1, 2]);
+    }
+
+    #[test]
+    fn readers_run_after_writers_added_later() {
+        let nodes = [
+            node(|node| {
+                node.read("shadow").write(RenderGraph::OUTPUT);
+            }),
+            node(|node| {
+                node.create_texture("shadow", TextureDesc::default());
+            }),
+        ];
+
+        assert_eq!(order_of(&nodes), [1, 0]);
+    }
+
+    #[test]
+    fn readers_see_the_last_earlier_write() {
+        // the second writer runs after the first, the reader after both
+        let nodes = [
+            node(|node| {
+                node.create_texture("a", TextureDesc::default());
+            }),
+            node(|node| {
+                node.write("a");
+            }),
+            node(|node| {
+                node.read("a");
+            }),
+        ];
+
+        assert_eq!(order_of(&nodes), [0, 1, 2]);
+    }
+
+    #[test]
+    fn writes_wait_for_earlier_readers() {
+        // the reader of `a` is pushed back by `b`, the later write of `a` has to wait
+        // for it
+        let nodes = [
+            node(|node| {
+                node.create_texture("a", TextureDesc::default());
+            }),
+            node(|node| {
+                node.read("a").read("b");
+            }),
+            node(|node| {
+                node.write("a");
+            }),
+            node(|node| {
+                node.create_texture("b", TextureDesc::default());
+            }),
+        ];
+
+        assert_eq!(order_of(&nodes), [0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn cycles_fall_back_to_added_order() {
+        let nodes = [
+            node(|node| {
+                node.read("a").create_texture("b", TextureDesc::default());
+            }),
+            node(|node| {
+                node.read("b").create_texture("a", TextureDesc::default());
+            }),
+            node(|node| {
+                node.write(RenderGraph::OUTPUT);
+            }),
+        ];
+
+        // the node outside the cycle is ready first
+        assert_eq!(order_of(&nodes), [2, 0, 1]);
+    }
+
+    #[test]
+    fn missing_resources_are_reported() {
+        let nodes = [
+            node(|node| {
+                node.create_texture("a", TextureDesc::default())
+                    .write(RenderGraph::OUTPUT);
+            }),
+            node(|node| {
+                node.read("a").read("typo").write("typo");
+            }),
+            node(|node| {
+                node.create_buffer(
+                    "buffer",
+                    BufferDesc {
+                        size: 4,
+                        usage: wgpu::BufferUsage::UNIFORM,
+                    },
+                );
+            }),
+            node(|node| {
+                node.read("buffer");
+            }),
+        ];
+        let builders: Vec<_> = nodes.iter().collect();
+
+        assert_eq!(missing_resources(&builders), [(1, "typo")]);
+    }
+
+    #[test]
+    fn last_use_follows_run_order() {
+        let nodes = [
+            node(|node| {
+                node.read("a");
+            }),
+            node(|node| {
+                node.create_texture("a", TextureDesc::default());
+            }),
+            node(|node| {
+                node.write(RenderGraph::OUTPUT);
+            }),
+        ];
+        let builders: Vec<_> = nodes.iter().collect();
+        let order = order(&builders);
+
+        assert_eq!(order, [1, 0, 2]);
+        // the reader runs second, after the node creating `a`
+        assert_eq!(last_use(&builders, &order, "a"), 1);
+        assert_eq!(last_use(&builders, &order, RenderGraph::OUTPUT), 2);
+    }
+
+    #[test]
+    fn pooled_resources_are_reused_after_their_last_use() {
+        let mut pool: Vec<Pooled<u32, usize>> = Vec::new();
+        let mut created = 0;
+        let mut create = |_: &u32| {
+            created += 1;
+            created
+        };
+
+        // busy from the node at position 0 until its last reader at 2
+        let a = reserve(&mut pool, 1, 0, 2, &mut create);
+        // overlaps `a`
+        let b = reserve(&mut pool, 1, 1, 1, &mut create);
+        // `a` is still read at 2, `b` is free
+        let c = reserve(&mut pool, 1, 2, 3, &mut create);
+        // `a` is free again
+        let d = reserve(&mut pool, 1, 3, 3, &mut create);
+        // different key
+        let e = reserve(&mut pool, 2, 4, 4, &mut create);
+        // never read, only lives for the node creating it
+        let f = reserve(&mut pool, 1, 4, 0, &mut create);
+
+        assert_ne!(a, b);
+        assert_eq!(c, b);
+        assert_eq!(d, a);
+        assert!(e != a && e != b);
+        assert_eq!(f, a);
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool[f].busy_until, Some(4));
+        assert_eq!(created, 3);
+    }
+}
@@ -1,201 +1,588 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    time::{Duration, Instant},
+};
 
-use bytemuck::{bytes_of, cast_slice};
-use glam::Mat4;
+use bytemuck::{cast_slice, cast_slice_mut, Pod};
+use glam::{Mat4, Vec2, Vec3, Vec4};
 use scissor::{mesh::Mesh, Config, Shape};
-use wgpu::util::DeviceExt;
 
-use crate::{primary_pipeline::primary_pipeline, RenderInstance, Swapchain, Transform};
+use crate::{
+    gradient_pipeline::{gradient_pipeline, gradient_uniforms, GRADIENT_UNIFORMS_SIZE},
+    hit_test::{mesh_contains, pixel_to_local},
+    mask_pipeline::mask_pipeline,
+    post::apply_effects,
+    primary_pipeline::{primary_pipeline, primary_uniforms, DEPTH_FORMAT},
+    render_graph::BuiltinNode,
+    sprite::{sprite_vertices, SPRITE_INDICES},
+    sprite_pipeline::{sprite_pipeline, sprite_texture},
+    text::{decoration_mesh, pixels_per_unit, text_batches},
+    Aabb, AtlasRegion, BlendMode, Camera, Font, GlyphCache, Gradient, Layer, MaskMode, Material,
+    MeshHandle, OrthographicCamera, Paint, PostEffect, Rect, RenderGraph, RenderInstance,
+    RenderStats, Sampler, ScaleMode, SpriteOptions, Swapchain, TextDecorationKind, TextLayout,
+    TextLayoutOptions, TextSpan, Texture, TextureAtlas, TextureDesc, UploadBuffer, Viewport,
+};
 
-#[derive(Clone, Debug)]
-pub enum ScaleMode {
-    /// Scale camera to be (aspect * size, size).
-    Aspect,
-    /// Don't scale the camera.
-    None,
-}
-
-#[derive(Clone, Debug)]
-pub struct OrthographicCamera {
-    pub left: f32,
-    pub bottom: f32,
-    pub right: f32,
-    pub top: f32,
-    pub near: f32,
-    pub far: f32,
-    pub size: f32,
-    pub scale_mode: ScaleMode,
-    pub transform: Transform,
+#[derive(Debug)]
+pub struct Pipelines {
+    /// Indexed by [`BlendMode`].
+    pub primary: Vec<wgpu::RenderPipeline>,
+    pub primary_uniforms: wgpu::BindGroupLayout,
+    /// Indexed by [`BlendMode`].
+    pub sprite: Vec<wgpu::RenderPipeline>,
+    pub sprite_texture: wgpu::BindGroupLayout,
+    /// Indexed by [`BlendMode`].
+    pub gradient: Vec<wgpu::RenderPipeline>,
+    pub gradient_uniforms: wgpu::BindGroupLayout,
+    /// Raises the mask level of pixels covered by a mask shape.
+    pub mask_increment: wgpu::RenderPipeline,
+    /// Lowers the mask level of pixels covered by a mask shape.
+    pub mask_decrement: wgpu::RenderPipeline,
 }
 
-impl Default for OrthographicCamera {
+impl Pipelines {
     #[inline]
-    fn default() -> Self {
+    pub fn new(instance: &RenderInstance, target_format: wgpu::TextureFormat) -> Self {
+        let primary_uniforms = primary_uniforms(instance);
+        let sprite_texture = sprite_texture(instance);
+        let gradient_uniforms = gradient_uniforms(instance);
+
+        let primary = BlendMode::ALL
+            .iter()
+            .map(|&mode| primary_pipeline(instance, target_format, &primary_uniforms, mode))
+            .collect();
+        let sprite = BlendMode::ALL
+            .iter()
+            .map(|&mode| {
+                sprite_pipeline(
+                    instance,
+                    target_format,
+                    &primary_uniforms,
+                    &sprite_texture,
+                    mode,
+                )
+            })
+            .collect();
+        let gradient = BlendMode::ALL
+            .iter()
+            .map(|&mode| {
+                gradient_pipeline(
+                    instance,
+                    target_format,
+                    &primary_uniforms,
+                    &gradient_uniforms,
+                    mode,
+                )
+            })
+            .collect();
+        let mask_increment = mask_pipeline(
+            instance,
+            target_format,
+            &primary_uniforms,
+            wgpu::StencilOperation::IncrementClamp,
+        );
+        let mask_decrement = mask_pipeline(
+            instance,
+            target_format,
+            &primary_uniforms,
+            wgpu::StencilOperation::DecrementClamp,
+        );
+
         Self {
-            left: -1.0,
-            bottom: -1.0,
-            right: 1.0,
-            top: 1.0,
-            near: -500.0,
-            far: 500.0,
-            size: 2.0,
-            scale_mode: ScaleMode::Aspect,
-            transform: Transform::IDENTITY,
+            primary,
+            primary_uniforms,
+            sprite,
+            sprite_texture,
+            gradient,
+            gradient_uniforms,
+            mask_increment,
+            mask_decrement,
         }
     }
 }
 
-impl OrthographicCamera {
-    #[inline]
-    pub fn proj(&self, aspect: f32) -> Mat4 {
-        let mut camera = self.clone();
+pub enum RenderableKind<'a> {
+    Ui {
+        mesh: Cow<'a, Mesh>,
+    },
+    Handle {
+        mesh: &'a MeshHandle,
+    },
+    Sprite {
+        texture: &'a Texture,
+        rect: Rect,
+        options: SpriteOptions,
+    },
+    Text {
+        layout: Cow<'a, TextLayout>,
+        color: [f32; 4],
+    },
+    Material {
+        mesh: Cow<'a, Mesh>,
+        material: &'a Material,
+    },
+    Gradient {
+        mesh: Cow<'a, Mesh>,
+        gradient: Gradient,
+    },
+}
 
-        match self.scale_mode {
-            ScaleMode::Aspect => {
-                camera.left = -camera.size * aspect / 2.0;
-                camera.right = camera.size * aspect / 2.0;
-                camera.bottom = -camera.size / 2.0;
-                camera.top = camera.size / 2.0;
-            }
-            ScaleMode::None => {}
+impl<'a> RenderableKind<'a> {
+    /// Bounds in local space, `None` if unknown.
+    #[inline]
+    fn bounds(&self) -> Option<Aabb> {
+        match *self {
+            Self::Ui { ref mesh }
+            | Self::Material { ref mesh, .. }
+            | Self::Gradient { ref mesh, .. } => Aabb::from_mesh(mesh),
+            Self::Handle { mesh } => mesh.bounds,
+            Self::Sprite { rect, .. } => Some(Aabb::from_rect(rect)),
+            Self::Text { ref layout, .. } => Some(Aabb::from_rect(layout.bounds)),
         }
-
-        Mat4::orthographic_rh(
-            camera.left,
-            camera.right,
-            camera.bottom,
-            camera.top,
-            camera.near,
-            camera.far,
-        )
     }
+}
 
-    #[inline]
-    pub fn view(&self) -> Mat4 {
-        self.transform.matrix()
-    }
+pub struct Renderable<'a> {
+    pub kind: RenderableKind<'a>,
+    pub transform: Mat4,
+    pub camera: Mat4,
+    pub blend_mode: BlendMode,
+    pub layer: Layer,
+    /// Order within the layer, higher on top.
+    pub z_index: i32,
+    /// Clip rect in pixels, see [`Frame::push_clip_rect`].
+    pub clip: Option<Rect>,
+    /// Index of the innermost mask of the frame, see [`Frame::push_mask`].
+    pub mask: Option<usize>,
+    /// Part of the target drawn to, see [`Frame::push_viewport`].
+    pub viewport: Viewport,
+    /// Bounds in local space, used to cull draws outside the view.
+    pub bounds: Option<Aabb>,
+    /// Id reported by [`Frame::hit_test`].
+    pub id: Option<u64>,
+}
 
+impl<'a> Renderable<'a> {
+    /// Whether the renderable may be visible, renderables without bounds always are.
     #[inline]
-    pub fn view_proj(&self, aspect: f32) -> Mat4 {
-        self.view().inverse() * self.proj(aspect)
+    pub fn in_view(&self) -> bool {
+        self.bounds.map_or(true, |bounds| {
+            bounds.in_frustum(self.camera * self.transform)
+        })
     }
-}
-
-#[derive(Debug)]
-pub struct Pipelines {
-    pub primary: wgpu::RenderPipeline,
-    pub primary_uniforms: wgpu::BindGroupLayout,
-}
 
-impl Pipelines {
+    /// Whether the renderable covers `pixel`, tested on its local plane z = 0.
+    ///
+    /// Meshes are tested triangle by triangle, uploaded ones against the copy kept by
+    /// [`RenderInstance::upload_mesh_hit_testable`] and by their bounds otherwise. Masks
+    /// aren't taken into account.
     #[inline]
-    pub fn new(instance: &RenderInstance, target_format: wgpu::TextureFormat) -> Self {
-        let (primary, primary_uniforms) = primary_pipeline(instance, target_format);
+    pub fn hit_test(&self, pixel: Vec2) -> bool {
+        if !self.viewport.rect.contains(pixel) {
+            return false;
+        }
 
-        Self {
-            primary,
-            primary_uniforms,
+        if let Some(clip) = self.clip {
+            if !clip.contains(pixel) {
+                return false;
+            }
         }
-    }
-}
 
-#[derive(Debug)]
-pub struct RenderTextures {
-    pub primary_image: wgpu::TextureView,
-    pub primary_depth: wgpu::TextureView,
-}
+        let point = match pixel_to_local(self.camera * self.transform, pixel, &self.viewport) {
+            Some(point) => point,
+            None => return false,
+        };
 
-impl RenderTextures {
-    #[inline]
-    pub fn new(
-        instance: &RenderInstance,
-        target_format: wgpu::TextureFormat,
-        width: u32,
-        height: u32,
-    ) -> Self {
-        fn texture(
-            instance: &RenderInstance,
-            format: wgpu::TextureFormat,
-            sample_count: u32,
-            width: u32,
-            height: u32,
-        ) -> wgpu::TextureView {
-            let texture = instance.device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("render texture"),
-                format,
-                dimension: wgpu::TextureDimension::D2,
-                size: wgpu::Extent3d {
-                    width,
-                    height,
-                    depth_or_array_layers: 1,
-                },
-                sample_count,
-                mip_level_count: 1,
-                usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
-            });
+        if let Some(bounds) = self.bounds {
+            let bounds = Rect::new(bounds.min.truncate(), bounds.max.truncate());
 
-            texture.create_view(&wgpu::TextureViewDescriptor {
-                label: None,
-                aspect: wgpu::TextureAspect::All,
-                format: None,
-                dimension: None,
-                array_layer_count: None,
-                base_array_layer: 0,
-                mip_level_count: None,
-                base_mip_level: 0,
-            })
+            if !bounds.contains(point) {
+                return false;
+            }
         }
 
-        Self {
-            primary_image: texture(instance, target_format, 8, width, height),
-            primary_depth: texture(instance, wgpu::TextureFormat::Depth24Plus, 8, width, height),
+        match self.kind {
+            RenderableKind::Ui { ref mesh }
+            | RenderableKind::Material { ref mesh, .. }
+            | RenderableKind::Gradient { ref mesh, .. } => {
+                mesh_contains(cast_slice(&mesh.vertices), &mesh.indices, point)
+            }
+            RenderableKind::Handle { mesh } => match mesh.hit_test_data {
+                Some((ref vertices, ref indices)) => mesh_contains(vertices, indices, point),
+                None => true,
+            },
+            RenderableKind::Sprite { rect, .. } => rect.contains(point),
+            RenderableKind::Text { ref layout, .. } => layout.bounds.contains(point),
         }
     }
 }
 
-pub enum Renderable<'a> {
-    Ui {
-        mesh: Cow<'a, Mesh>,
-        transform: Mat4,
-        camera: Mat4,
-    },
+/// A shape drawn into the stencil buffer, see [`Frame::push_mask`].
+struct Mask<'a> {
+    mesh: Cow<'a, Mesh>,
+    mode: MaskMode,
+    transform: Mat4,
+    camera: Mat4,
+    viewport: Viewport,
+    /// The enclosing mask.
+    parent: Option<usize>,
 }
 
 pub struct Frame<'a> {
-    aspect: f32,
+    width: u32,
+    height: u32,
     pub config: Config,
+    /// Largest distance in pixels tessellated curves may be off by on screen.
+    ///
+    /// When set, the resolution of [`Frame::config`] is derived for every shape from how
+    /// large it's drawn, instead of being the same at every zoom level.
+    pub max_screen_error: Option<f32>,
+    /// Finest resolution [`Frame::max_screen_error`] may derive, bounds the size of meshes
+    /// drawn close to the camera or zoomed in far.
+    pub min_resolution: f32,
     pub clear_color: [f32; 4],
+    /// Blend mode of the following draws.
+    pub blend_mode: BlendMode,
+    /// Layer of the following draws.
+    pub layer: Layer,
+    /// Z-index of the following draws, orders draws within a layer.
+    pub z_index: i32,
+    /// Id of the following draws, see [`Frame::hit_test`].
+    pub id: Option<u64>,
+    /// Layers not drawn, see [`Frame::set_layer_visible`].
+    hidden_layers: HashSet<Layer>,
+    /// Stats of the frame rendered before this one, see [`Renderer::frame`].
+    last_stats: Option<RenderStats>,
+    /// Nested clip rects in pixels, the last is the intersection of all of them.
+    clip_rects: Vec<Rect>,
+    /// Time spent generating meshes of shapes, see [`RenderStats::tessellation`].
+    tessellation: Duration,
+    /// Nested viewports, the last is the one drawn to.
+    viewports: Vec<Viewport>,
+    /// Every mask pushed this frame.
+    masks: Vec<Mask<'a>>,
+    /// The innermost mask of the following draws.
+    mask: Option<usize>,
     renderables: Vec<Renderable<'a>>,
 }
 
 impl<'a> Frame<'a> {
+    pub const DEFAULT_MIN_RESOLUTION: f32 = 0.001;
+
+    /// Creates a frame for the target it's rendered to, `width` by `height` pixels, see
+    /// also [`Renderer::frame`].
     #[inline]
-    pub fn new(aspect: f32) -> Self {
+    pub fn new(width: u32, height: u32) -> Self {
         Self {
-            aspect,
+            width,
+            height,
             config: Config::default(),
+            max_screen_error: None,
+            min_resolution: Self::DEFAULT_MIN_RESOLUTION,
             clear_color: [1.0; 4],
+            blend_mode: BlendMode::Normal,
+            layer: Layer::DEFAULT,
+            z_index: 0,
+            id: None,
+            hidden_layers: HashSet::new(),
+            last_stats: None,
+            clip_rects: Vec::new(),
+            tessellation: Duration::default(),
+            viewports: Vec::new(),
+            masks: Vec::new(),
+            mask: None,
             renderables: Vec::new(),
         }
     }
 
+    /// Aspect ratio of the current viewport.
     #[inline]
     pub fn aspect(&self) -> f32 {
-        self.aspect
+        self.viewport().aspect()
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Shows or hides every draw on `layer` in this frame, layers hidden with
+    /// [`Renderer::set_layer_visible`] stay hidden.
+    #[inline]
+    pub fn set_layer_visible(&mut self, layer: Layer, visible: bool) {
+        if visible {
+            self.hidden_layers.remove(&layer);
+        } else {
+            self.hidden_layers.insert(layer);
+        }
+    }
+
+    #[inline]
+    pub fn layer_visible(&self, layer: Layer) -> bool {
+        !self.hidden_layers.contains(&layer)
+    }
+
+    /// Stats of the frame rendered before this one, for frames made with
+    /// [`Renderer::frame`] after a frame was rendered.
+    ///
+    /// Draw them with [`Frame::draw_stats`].
+    #[inline]
+    pub fn last_stats(&self) -> Option<RenderStats> {
+        self.last_stats
+    }
+
+    /// Ids of the draws made so far covering `pixel`, front to back.
+    ///
+    /// Only draws made while [`Frame::id`] was set are reported, draws on hidden layers
+    /// are skipped, see [`Renderable::hit_test`]. Frames made with [`Renderer::frame`]
+    /// know the layers hidden on the renderer.
+    #[inline]
+    pub fn hit_test(&self, pixel: Vec2) -> Vec<u64> {
+        draw_order(&self.renderables, &self.hidden_layers)
+            .into_iter()
+            .rev()
+            .map(|i| &self.renderables[i])
+            .filter_map(|renderable| renderable.id.filter(|_| renderable.hit_test(pixel)))
+            .collect()
+    }
+
+    /// The viewport of the following draws, the whole target unless one was pushed.
+    ///
+    /// Use it to convert between pixels and world space with
+    /// [`Camera::screen_to_world`] and [`Camera::world_to_screen`].
+    #[inline]
+    pub fn viewport(&self) -> Viewport {
+        self.viewports
+            .last()
+            .copied()
+            .unwrap_or_else(|| Viewport::new(self.width, self.height))
+    }
+
+    /// Draws the following draws into `viewport`, until the matching
+    /// [`Frame::pop_viewport`].
+    ///
+    /// Cameras use the aspect ratio of the viewport and draws are clipped to it, the
+    /// viewport is limited to the current viewport, so nested viewports stay inside the
+    /// enclosing ones.
+    #[inline]
+    pub fn push_viewport(&mut self, viewport: Viewport) {
+        let current = self.viewport();
+
+        self.viewports
+            .push(Viewport::from_rect(viewport.rect.intersect(&current.rect)));
+    }
+
+    /// Removes the last viewport pushed.
+    #[inline]
+    pub fn pop_viewport(&mut self) {
+        self.viewports.pop();
+    }
+
+    /// Clips the following draws to `rect` in pixels, from the top left corner of the
+    /// target with y pointing down, until the matching [`Frame::pop_clip_rect`].
+    ///
+    /// Nested clip rects are intersected with the enclosing ones.
+    #[inline]
+    pub fn push_clip_rect(&mut self, rect: Rect) {
+        let rect = match self.clip_rects.last() {
+            Some(clip) => clip.intersect(&rect),
+            None => rect,
+        };
+
+        self.clip_rects.push(rect);
     }
 
+    /// Clips the following draws to the screen space bounds of `rect` in the local space
+    /// of `transform`, see [`Frame::push_clip_rect`].
     #[inline]
-    pub fn draw_ui(
+    pub fn push_world_clip_rect(
+        &mut self,
+        rect: Rect,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        let matrix = camera.view_proj(self.aspect()) * transform.into();
+        let viewport = self.viewport();
+
+        let corners = [
+            rect.min,
+            Vec2::new(rect.max.x, rect.min.y),
+            rect.max,
+            Vec2::new(rect.min.x, rect.max.y),
+        ];
+
+        let pixels = corners.iter().map(|corner| {
+            let clip = matrix * corner.extend(0.0).extend(1.0);
+
+            viewport.ndc_to_pixel(Vec2::new(clip.x, clip.y) / clip.w)
+        });
+
+        let bounds = pixels.fold(
+            Rect::new(Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |bounds, pixel| Rect::new(bounds.min.min(pixel), bounds.max.max(pixel)),
+        );
+
+        self.push_clip_rect(bounds);
+    }
+
+    /// Removes the last clip rect pushed.
+    #[inline]
+    pub fn pop_clip_rect(&mut self) {
+        self.clip_rects.pop();
+    }
+
+    /// The current clip rect in pixels, if any.
+    #[inline]
+    pub fn clip_rect(&self) -> Option<Rect> {
+        self.clip_rects.last().copied()
+    }
+
+    /// Masks the following draws to the inside or outside of `shape`, until the matching
+    /// [`Frame::pop_mask`].
+    ///
+    /// Nested masks are intersected with the enclosing ones, up to 255 levels deep.
+    /// Masks apply to the draws made while they're pushed, whatever their layer.
+    #[inline]
+    pub fn push_mask(
+        &mut self,
+        shape: &impl Shape<Input = (), Output = Mesh>,
+        mode: MaskMode,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        let transform = transform.into();
+        let mesh = self.generate(shape, transform, camera);
+
+        self.push_mask_inner(Cow::Owned(mesh), mode, transform, camera);
+    }
+
+    /// Masks the following draws to the inside or outside of `mesh`, see
+    /// [`Frame::push_mask`].
+    #[inline]
+    pub fn push_mask_mesh(
         &mut self,
         mesh: &'a Mesh,
+        mode: MaskMode,
         transform: impl Into<Mat4>,
-        camera: &OrthographicCamera,
+        camera: &impl Camera,
     ) {
-        self.renderables.push(Renderable::Ui {
-            mesh: Cow::Borrowed(mesh),
-            transform: transform.into(),
-            camera: camera.view_proj(self.aspect),
+        self.push_mask_inner(Cow::Borrowed(mesh), mode, transform.into(), camera);
+    }
+
+    /// Removes the last mask pushed.
+    #[inline]
+    pub fn pop_mask(&mut self) {
+        self.mask = self.mask.and_then(|mask| self.masks[mask].parent);
+    }
+
+    #[inline]
+    fn push_mask_inner(
+        &mut self,
+        mesh: Cow<'a, Mesh>,
+        mode: MaskMode,
+        transform: Mat4,
+        camera: &impl Camera,
+    ) {
+        self.masks.push(Mask {
+            mesh,
+            mode,
+            transform,
+            camera: camera.view_proj(self.aspect()),
+            viewport: self.viewport(),
+            parent: self.mask,
         });
+
+        self.mask = Some(self.masks.len() - 1);
+    }
+
+    /// Draws `stats` in the top left corner of the current viewport, `size` is the
+    /// height of the font in pixels.
+    #[inline]
+    pub fn draw_stats(&mut self, stats: &RenderStats, font: &Font, size: f32, color: [f32; 4]) {
+        let viewport = self.viewport().rect;
+        let camera = OrthographicCamera {
+            left: 0.0,
+            bottom: -viewport.height(),
+            right: viewport.width(),
+            top: 0.0,
+            scale_mode: ScaleMode::None,
+            ..Default::default()
+        };
+
+        let layer = std::mem::replace(&mut self.layer, Layer(i32::MAX));
+        self.draw_text(
+            &stats.to_string(),
+            font,
+            size,
+            color,
+            Mat4::from_translation(Vec3::new(size / 2.0, -size * 1.5, 0.0)),
+            &camera,
+        );
+        self.layer = layer;
+    }
+
+    /// Generates the mesh of `shape` with [`Frame::config`], adapting the resolution to
+    /// [`Frame::max_screen_error`] when it's set.
+    #[inline]
+    fn generate(
+        &mut self,
+        shape: &impl Shape<Input = (), Output = Mesh>,
+        transform: Mat4,
+        camera: &impl Camera,
+    ) -> Mesh {
+        let resolution = self.config.resolution;
+
+        if let Some(max_error) = self.max_screen_error {
+            let matrix = camera.view_proj(self.aspect()) * transform;
+            let pixels_per_unit = screen_scale(matrix, self.viewport().rect.size());
+
+            if pixels_per_unit > 0.0 {
+                self.config.resolution = (max_error / pixels_per_unit).max(self.min_resolution);
+            }
+        }
+
+        let start = Instant::now();
+        let mesh = shape.generate(&self.config, ());
+        self.tessellation += start.elapsed();
+
+        self.config.resolution = resolution;
+
+        mesh
+    }
+
+    #[inline]
+    fn push(&mut self, kind: RenderableKind<'a>, transform: Mat4, camera: &impl Camera) {
+        self.renderables.push(Renderable {
+            bounds: kind.bounds(),
+            kind,
+            transform,
+            camera: camera.view_proj(self.aspect()),
+            blend_mode: self.blend_mode,
+            layer: self.layer,
+            z_index: self.z_index,
+            id: self.id,
+            clip: self.clip_rect(),
+            mask: self.mask,
+            viewport: self.viewport(),
+        });
+    }
+
+    #[inline]
+    pub fn draw_ui(&mut self, mesh: &'a Mesh, transform: impl Into<Mat4>, camera: &impl Camera) {
+        self.push(
+            RenderableKind::Ui {
+                mesh: Cow::Borrowed(mesh),
+            },
+            transform.into(),
+            camera,
+        );
     }
 
     #[inline]
@@ -203,60 +590,819 @@ impl<'a> Frame<'a> {
         &mut self,
         shape: &impl Shape<Input = (), Output = Mesh>,
         transform: impl Into<Mat4>,
-        camera: &OrthographicCamera,
+        camera: &impl Camera,
     ) {
-        self.renderables.push(Renderable::Ui {
-            mesh: Cow::Owned(shape.generate(&self.config, ())),
-            transform: transform.into(),
-            camera: camera.view_proj(self.aspect),
-        });
+        let transform = transform.into();
+        let mesh = self.generate(shape, transform, camera);
+
+        self.push(
+            RenderableKind::Ui {
+                mesh: Cow::Owned(mesh),
+            },
+            transform,
+            camera,
+        );
+    }
+
+    /// Draws `shape` filled with `paint`, replacing the colors of its vertices.
+    ///
+    /// Gradients are evaluated per pixel in the local space of the shape.
+    #[inline]
+    pub fn draw_shape_with_paint(
+        &mut self,
+        shape: &impl Shape<Input = (), Output = Mesh>,
+        paint: impl Into<Paint>,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        let transform = transform.into();
+        let mut mesh = self.generate(shape, transform, camera);
+
+        let kind = match paint.into() {
+            Paint::Solid(color) => {
+                let vertices: &mut [[f32; 7]] = cast_slice_mut(&mut mesh.vertices);
+
+                for vertex in vertices {
+                    vertex[3..].copy_from_slice(&color);
+                }
+
+                RenderableKind::Ui {
+                    mesh: Cow::Owned(mesh),
+                }
+            }
+            Paint::Gradient(gradient) => RenderableKind::Gradient {
+                mesh: Cow::Owned(mesh),
+                gradient,
+            },
+        };
+
+        self.push(kind, transform, camera);
+    }
+
+    /// Draws `shape` shaded with `material`.
+    #[inline]
+    pub fn draw_shape_with_material(
+        &mut self,
+        shape: &impl Shape<Input = (), Output = Mesh>,
+        material: &'a Material,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        let transform = transform.into();
+        let mesh = self.generate(shape, transform, camera);
+
+        self.push(
+            RenderableKind::Material {
+                mesh: Cow::Owned(mesh),
+                material,
+            },
+            transform,
+            camera,
+        );
+    }
+
+    /// Draws a mesh previously uploaded with [`RenderInstance::upload_mesh`].
+    #[inline]
+    pub fn draw_mesh_handle(
+        &mut self,
+        handle: &'a MeshHandle,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        self.push(
+            RenderableKind::Handle { mesh: handle },
+            transform.into(),
+            camera,
+        );
+    }
+
+    /// Draws `texture` stretched over `rect`.
+    #[inline]
+    pub fn draw_sprite(
+        &mut self,
+        texture: &'a Texture,
+        rect: Rect,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        self.draw_sprite_with_options(texture, rect, SpriteOptions::default(), transform, camera);
+    }
+
+    #[inline]
+    pub fn draw_sprite_with_options(
+        &mut self,
+        texture: &'a Texture,
+        rect: Rect,
+        options: SpriteOptions,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        self.push(
+            RenderableKind::Sprite {
+                texture,
+                rect,
+                options,
+            },
+            transform.into(),
+            camera,
+        );
+    }
+
+    /// Draws a region of `atlas` stretched over `rect`.
+    #[inline]
+    pub fn draw_atlas_sprite(
+        &mut self,
+        atlas: &'a TextureAtlas,
+        region: AtlasRegion,
+        rect: Rect,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        let (texture, options) = atlas.sprite(region, SpriteOptions::default());
+        self.draw_sprite_with_options(texture, rect, options, transform, camera);
+    }
+
+    /// Draws `text` starting at the origin of `transform`, with the baseline of the first
+    /// line on the x axis. `size` is the height of the font in local units.
+    #[inline]
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        font: &Font,
+        size: f32,
+        color: [f32; 4],
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        let layout = TextLayout::new(text, font, size, &TextLayoutOptions::default());
+
+        self.push(
+            RenderableKind::Text {
+                layout: Cow::Owned(layout),
+                color,
+            },
+            transform.into(),
+            camera,
+        );
+    }
+
+    /// Draws `spans` laid out together with [`TextLayout::rich`].
+    #[inline]
+    pub fn draw_rich_text(
+        &mut self,
+        spans: &[TextSpan],
+        options: &TextLayoutOptions,
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        let layout = TextLayout::rich(spans, options);
+
+        self.push(
+            RenderableKind::Text {
+                layout: Cow::Owned(layout),
+                color: [1.0; 4],
+            },
+            transform.into(),
+            camera,
+        );
+    }
+
+    /// Draws text laid out with [`TextLayout::new`] or [`TextLayout::rich`], span colors
+    /// are multiplied with `color`.
+    #[inline]
+    pub fn draw_text_layout(
+        &mut self,
+        layout: &'a TextLayout,
+        color: [f32; 4],
+        transform: impl Into<Mat4>,
+        camera: &impl Camera,
+    ) {
+        self.push(
+            RenderableKind::Text {
+                layout: Cow::Borrowed(layout),
+                color,
+            },
+            transform.into(),
+            camera,
+        );
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DrawPipeline {
+    Primary,
+    Sprite(TextureKey),
+    Material(MaterialKey),
+    /// Offset of the gradient uniforms.
+    Gradient(u32),
+    /// Changes the mask level, raising it if `true`.
+    Mask(bool),
+}
+
+/// A texture bound with a specific sampler.
+type TextureKey = (u64, Sampler);
+
 #[derive(Debug)]
-pub struct PrimaryData {
-    pub uniform_buffer: wgpu::Buffer,
-    pub uniform_bind_group: wgpu::BindGroup,
-    pub vertex_buffer: wgpu::Buffer,
-    pub vertex_buffer_size: usize,
-    pub index_buffer: wgpu::Buffer,
-    pub index_buffer_size: usize,
-    pub indices: u32,
+struct TextureBindGroup {
+    bind_group: wgpu::BindGroup,
+    last_used: u64,
+}
+
+/// Bind groups of textures drawn in recent frames.
+#[derive(Debug, Default)]
+struct TextureBindGroups {
+    samplers: HashMap<Sampler, wgpu::Sampler>,
+    bind_groups: HashMap<TextureKey, TextureBindGroup>,
+    frame_index: u64,
+}
+
+impl TextureBindGroups {
+    /// Gets or creates the bind group for `texture` sampled with `sampler`.
+    #[inline]
+    fn get(
+        &mut self,
+        instance: &RenderInstance,
+        layout: &wgpu::BindGroupLayout,
+        texture: &Texture,
+        sampler: Sampler,
+    ) -> TextureKey {
+        let key = (texture.id(), sampler);
+        let frame_index = self.frame_index;
+
+        if let Some(bind_group) = self.bind_groups.get_mut(&key) {
+            bind_group.last_used = frame_index;
+            return key;
+        }
+
+        let wgpu_sampler = self
+            .samplers
+            .entry(sampler)
+            .or_insert_with(|| sampler.create(instance));
+
+        let bind_group = instance
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("sprite texture"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(texture.view()),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(wgpu_sampler),
+                    },
+                ],
+            });
+
+        self.bind_groups.insert(
+            key,
+            TextureBindGroup {
+                bind_group,
+                last_used: frame_index,
+            },
+        );
+
+        key
+    }
+
+    #[inline]
+    fn bind_group(&self, key: &TextureKey) -> &wgpu::BindGroup {
+        &self.bind_groups[key].bind_group
+    }
+
+    /// Drops bind groups of textures that haven't been drawn in `trim_frames` frames.
+    #[inline]
+    fn end_frame(&mut self, trim_frames: u32) {
+        let frame_index = self.frame_index;
+
+        self.bind_groups
+            .retain(|_, bind_group| frame_index - bind_group.last_used < trim_frames as u64);
+        self.frame_index += 1;
+    }
+}
+
+/// A material drawn to a target format with a sample count and blend mode.
+type MaterialKey = (u64, wgpu::TextureFormat, u32, BlendMode);
+
+#[derive(Debug)]
+struct MaterialPipeline {
+    pipeline: wgpu::RenderPipeline,
+    last_used: u64,
+}
+
+/// Pipelines of materials drawn in recent frames.
+#[derive(Debug, Default)]
+struct MaterialPipelines {
+    pipelines: HashMap<MaterialKey, MaterialPipeline>,
+    frame_index: u64,
+}
+
+impl MaterialPipelines {
+    /// Gets or creates the pipeline of `material`.
+    #[inline]
+    fn get(
+        &mut self,
+        instance: &RenderInstance,
+        uniforms: &wgpu::BindGroupLayout,
+        material: &Material,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        blend_mode: BlendMode,
+    ) -> MaterialKey {
+        let key = (material.id(), format, sample_count, blend_mode);
+        let frame_index = self.frame_index;
+
+        let pipeline = self
+            .pipelines
+            .entry(key)
+            .or_insert_with(|| MaterialPipeline {
+                pipeline: material.pipeline(instance, format, sample_count, blend_mode, uniforms),
+                last_used: frame_index,
+            });
+
+        pipeline.last_used = frame_index;
+
+        key
+    }
+
+    #[inline]
+    fn pipeline(&self, key: &MaterialKey) -> &wgpu::RenderPipeline {
+        &self.pipelines[key].pipeline
+    }
+
+    /// Drops pipelines of materials that haven't been drawn in `trim_frames` frames.
+    #[inline]
+    fn end_frame(&mut self, trim_frames: u32) {
+        let frame_index = self.frame_index;
+
+        self.pipelines
+            .retain(|_, pipeline| frame_index - pipeline.last_used < trim_frames as u64);
+        self.frame_index += 1;
+    }
+}
+
+struct Draw<'a> {
+    uniform_offset: u32,
+    pipeline: DrawPipeline,
+    blend_mode: BlendMode,
+    clip: Option<Rect>,
+    /// Mask level the draw applies to.
+    stencil: u32,
+    /// Viewport in pixels.
+    viewport: Rect,
+    geometry: DrawGeometry<'a>,
+}
+
+/// Where the geometry of a single draw lives.
+#[derive(Clone)]
+enum DrawGeometry<'a> {
+    /// Ranges into the shared per-frame vertex and index buffers.
+    Shared {
+        vertices: Range<u64>,
+        indices: Range<u64>,
+        vertex_count: u32,
+        count: u32,
+    },
+    Handle(&'a MeshHandle),
 }
 
 #[derive(Debug)]
 pub struct Renderer {
     instance: RenderInstance,
     swapchain: Swapchain,
-    textures: RenderTextures,
     pipelines: Pipelines,
+    /// Per draw uniforms, addressed with dynamic offsets.
+    ///
     /// # Layout
     /// 0. Transform matrix
     /// 64. Camera matrix
-    primary_uniforms: Vec<PrimaryData>,
+    uniforms: UploadBuffer,
+    uniform_bind_group: wgpu::BindGroup,
+    uniform_generation: u64,
+    /// Per draw gradient uniforms, addressed with dynamic offsets.
+    gradients: UploadBuffer,
+    gradient_bind_group: wgpu::BindGroup,
+    gradient_generation: u64,
+    vertices: UploadBuffer,
+    indices: UploadBuffer,
+    texture_bind_groups: TextureBindGroups,
+    material_pipelines: MaterialPipelines,
+    glyph_cache: GlyphCache,
+    effects: Vec<PostEffect>,
+    /// Copies the scene to the output when it's read without post-processing.
+    copy_effect: PostEffect,
+    post_sampler: wgpu::Sampler,
+    hidden_layers: HashSet<Layer>,
+    graph: RenderGraph,
+    /// Renderables outside the view last frame.
+    culled: usize,
+    last_stats: Option<RenderStats>,
 }
 
 impl Renderer {
     #[inline]
     pub fn new(instance: &RenderInstance, swapchain: Swapchain) -> Self {
+        let pipelines = Pipelines::new(instance, swapchain.format());
+        let uniforms = UploadBuffer::new(instance, "primary uniforms", wgpu::BufferUsage::UNIFORM);
+        let gradients =
+            UploadBuffer::new(instance, "gradient uniforms", wgpu::BufferUsage::UNIFORM);
+        let gradient_bind_group = uniform_bind_group(
+            instance,
+            "gradient uniforms",
+            &pipelines.gradient_uniforms,
+            &gradients,
+            GRADIENT_UNIFORMS_SIZE,
+        );
+        let uniform_bind_group = uniform_bind_group(
+            instance,
+            "primary uniforms",
+            &pipelines.primary_uniforms,
+            &uniforms,
+            PRIMARY_UNIFORMS_SIZE,
+        );
+
         Self {
             instance: instance.clone(),
-            textures: RenderTextures::new(
+            pipelines,
+            swapchain,
+            uniform_generation: uniforms.generation(),
+            uniforms,
+            uniform_bind_group,
+            gradient_generation: gradients.generation(),
+            gradients,
+            gradient_bind_group,
+            vertices: UploadBuffer::new(
                 instance,
-                swapchain.format(),
-                swapchain.width(),
-                swapchain.height(),
+                "primary vertex buffer",
+                wgpu::BufferUsage::VERTEX,
             ),
-            pipelines: Pipelines::new(instance, swapchain.format()),
-            swapchain,
-            primary_uniforms: Vec::new(),
+            indices: UploadBuffer::new(instance, "primary index buffer", wgpu::BufferUsage::INDEX),
+            texture_bind_groups: TextureBindGroups::default(),
+            material_pipelines: MaterialPipelines::default(),
+            glyph_cache: GlyphCache::new(),
+            effects: Vec::new(),
+            copy_effect: PostEffect::copy(instance),
+            post_sampler: Sampler::LINEAR.create(instance),
+            hidden_layers: HashSet::new(),
+            graph: RenderGraph::new(),
+            culled: 0,
+            last_stats: None,
+        }
+    }
+
+    /// Sets how many frames in a row gpu buffers have to be mostly unused before
+    /// they are shrunk, and how long unused glyphs, bind groups and pipelines stay
+    /// cached.
+    #[inline]
+    pub fn set_trim_frames(&mut self, frames: u32) {
+        self.uniforms.trim_frames = frames;
+        self.gradients.trim_frames = frames;
+        self.vertices.trim_frames = frames;
+        self.indices.trim_frames = frames;
+    }
+
+    /// Shows or hides every draw on `layer`.
+    #[inline]
+    pub fn set_layer_visible(&mut self, layer: Layer, visible: bool) {
+        if visible {
+            self.hidden_layers.remove(&layer);
+        } else {
+            self.hidden_layers.insert(layer);
         }
     }
 
     #[inline]
-    pub fn render(&mut self, frame: Frame<'_>) -> Result<(), wgpu::SwapChainError> {
+    pub fn layer_visible(&self, layer: Layer) -> bool {
+        !self.hidden_layers.contains(&layer)
+    }
+
+    /// How many renderables of the last frame were skipped for being outside the view,
+    /// also reported in [`RenderStats::culled`].
+    #[inline]
+    pub fn culled_count(&self) -> usize {
+        self.culled
+    }
+
+    /// Creates a frame for the current target, with the layers hidden on the renderer
+    /// hidden, so [`Frame::hit_test`] skips them, and the stats of the last frame, see
+    /// [`Frame::last_stats`].
+    #[inline]
+    pub fn frame<'a>(&self) -> Frame<'a> {
+        let mut frame = Frame::new(self.width(), self.height());
+        frame.hidden_layers = self.hidden_layers.clone();
+        frame.last_stats = self.last_stats;
+        frame
+    }
+
+    /// Stats of the last frame rendered, also returned by [`Renderer::render`].
+    #[inline]
+    pub fn last_stats(&self) -> Option<RenderStats> {
+        self.last_stats
+    }
+
+    /// Adds a post-processing effect, run after the previously added ones.
+    #[inline]
+    pub fn push_effect(&mut self, effect: PostEffect) {
+        self.effects.push(effect);
+    }
+
+    /// Post-processing effects, run in order after the primary pass.
+    #[inline]
+    pub fn effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+
+    #[inline]
+    pub fn effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.effects
+    }
+
+    /// The passes rendering each frame, add nodes to draw shadows, overlays or offscreen
+    /// views.
+    #[inline]
+    pub fn graph(&self) -> &RenderGraph {
+        &self.graph
+    }
+
+    #[inline]
+    pub fn graph_mut(&mut self) -> &mut RenderGraph {
+        &mut self.graph
+    }
+
+    /// Whether any effect has to run, the primary pass then renders into an
+    /// intermediate texture instead of the swapchain.
+    #[inline]
+    fn post_processing(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.enabled && !effect.passes.is_empty())
+    }
+
+    #[inline]
+    pub fn render(&mut self, mut frame: Frame<'_>) -> Result<RenderStats, wgpu::SwapChainError> {
         let swapchain_frame = self.swapchain.current_frame()?;
 
+        let mut stats = RenderStats {
+            tessellation: frame.tessellation,
+            ..Default::default()
+        };
+        let upload_start = Instant::now();
+
+        let post_processing = self.post_processing();
+
+        self.uniforms.reset();
+        self.gradients.reset();
+        self.vertices.reset();
+        self.indices.reset();
+
+        frame.hidden_layers.extend(&self.hidden_layers);
+
+        let mut order = draw_order(&frame.renderables, &frame.hidden_layers);
+
+        let visible = order.len();
+        order.retain(|&i| frame.renderables[i].in_view());
+        stats.culled = visible - order.len();
+        self.culled = stats.culled;
+
+        // opaque front to back so hidden pixels are rejected early, the rest back to front
+        let (opaque, translucent): (Vec<_>, Vec<_>) = order
+            .iter()
+            .copied()
+            .enumerate()
+            .partition(|&(_, i)| frame.renderables[i].blend_mode == BlendMode::Opaque);
+
+        let mut draws = Vec::new();
+        let mut materials = HashMap::new();
+        let mut masks = MaskDraws::default();
+        let mut current_mask = None;
+        let mut current_masks = Vec::new();
+
+        for (painter_index, i) in opaque.into_iter().rev().chain(translucent) {
+            let renderable = &frame.renderables[i];
+
+            if renderable.mask != current_mask {
+                let target = mask_chain(&frame.masks, renderable.mask);
+                self.change_masks(
+                    &frame.masks,
+                    &current_masks,
+                    &target,
+                    &mut masks,
+                    &mut draws,
+                );
+
+                current_mask = renderable.mask;
+                current_masks = target;
+            }
+
+            let stencil = current_masks.len() as u32;
+            let camera = depth_slice(painter_index, order.len()) * renderable.camera;
+
+            let uniform_offset = self.uniforms.push(
+                cast_slice(&[renderable.transform, camera]),
+                wgpu::BIND_BUFFER_ALIGNMENT,
+            );
+
+            let uniform_offset = uniform_offset as u32;
+
+            match renderable.kind {
+                RenderableKind::Ui { ref mesh } => draws.push(Draw {
+                    uniform_offset,
+                    pipeline: DrawPipeline::Primary,
+                    blend_mode: renderable.blend_mode,
+                    clip: renderable.clip,
+                    stencil,
+                    viewport: renderable.viewport.rect,
+                    geometry: self.push_geometry(&mesh.vertices, &mesh.indices),
+                }),
+                RenderableKind::Handle { mesh } => draws.push(Draw {
+                    uniform_offset,
+                    pipeline: DrawPipeline::Primary,
+                    blend_mode: renderable.blend_mode,
+                    clip: renderable.clip,
+                    stencil,
+                    viewport: renderable.viewport.rect,
+                    geometry: DrawGeometry::Handle(mesh),
+                }),
+                RenderableKind::Sprite {
+                    texture,
+                    ref rect,
+                    ref options,
+                } => {
+                    let key = self.texture_bind_groups.get(
+                        &self.instance,
+                        &self.pipelines.sprite_texture,
+                        texture,
+                        options.sampler,
+                    );
+                    let vertices = sprite_vertices(rect, options);
+
+                    draws.push(Draw {
+                        uniform_offset,
+                        pipeline: DrawPipeline::Sprite(key),
+                        blend_mode: renderable.blend_mode,
+                        clip: renderable.clip,
+                        stencil,
+                        viewport: renderable.viewport.rect,
+                        geometry: self.push_geometry(&vertices, &SPRITE_INDICES),
+                    });
+                }
+                RenderableKind::Text { ref layout, color } => {
+                    let pixels_per_unit = pixels_per_unit(
+                        renderable.camera * renderable.transform,
+                        renderable.viewport.rect.size(),
+                    );
+
+                    let batches = text_batches(
+                        &self.instance,
+                        &mut self.glyph_cache,
+                        layout,
+                        color,
+                        pixels_per_unit,
+                    );
+
+                    let (vertices, indices) = decoration_mesh(layout, color, |kind| {
+                        kind == TextDecorationKind::Background
+                    });
+
+                    if !indices.is_empty() {
+                        draws.push(Draw {
+                            uniform_offset,
+                            pipeline: DrawPipeline::Primary,
+                            blend_mode: renderable.blend_mode,
+                            clip: renderable.clip,
+                            stencil,
+                            viewport: renderable.viewport.rect,
+                            geometry: self.push_geometry(&vertices, &indices),
+                        });
+                    }
+
+                    for batch in batches {
+                        let key = self.texture_bind_groups.get(
+                            &self.instance,
+                            &self.pipelines.sprite_texture,
+                            self.glyph_cache.atlas().page(batch.page),
+                            Sampler::LINEAR,
+                        );
+
+                        draws.push(Draw {
+                            uniform_offset,
+                            pipeline: DrawPipeline::Sprite(key),
+                            blend_mode: renderable.blend_mode,
+                            clip: renderable.clip,
+                            stencil,
+                            viewport: renderable.viewport.rect,
+                            geometry: self.push_geometry(&batch.vertices, &batch.indices),
+                        });
+                    }
+
+                    // lines are drawn over the glyphs
+                    let (vertices, indices) = decoration_mesh(layout, color, |kind| {
+                        kind != TextDecorationKind::Background
+                    });
+
+                    if !indices.is_empty() {
+                        draws.push(Draw {
+                            uniform_offset,
+                            pipeline: DrawPipeline::Primary,
+                            blend_mode: renderable.blend_mode,
+                            clip: renderable.clip,
+                            stencil,
+                            viewport: renderable.viewport.rect,
+                            geometry: self.push_geometry(&vertices, &indices),
+                        });
+                    }
+                }
+                RenderableKind::Material { ref mesh, material } => {
+                    let key = self.material_pipelines.get(
+                        &self.instance,
+                        &self.pipelines.primary_uniforms,
+                        material,
+                        self.swapchain.format(),
+                        SAMPLE_COUNT,
+                        renderable.blend_mode,
+                    );
+                    materials.insert(material.id(), material);
+
+                    draws.push(Draw {
+                        uniform_offset,
+                        pipeline: DrawPipeline::Material(key),
+                        blend_mode: renderable.blend_mode,
+                        clip: renderable.clip,
+                        stencil,
+                        viewport: renderable.viewport.rect,
+                        geometry: self.push_geometry(&mesh.vertices, &mesh.indices),
+                    });
+                }
+                RenderableKind::Gradient {
+                    ref mesh,
+                    ref gradient,
+                } => {
+                    let gradient_offset = self.gradients.push(
+                        bytemuck::bytes_of(&gradient.uniforms()),
+                        wgpu::BIND_BUFFER_ALIGNMENT,
+                    );
+
+                    draws.push(Draw {
+                        uniform_offset,
+                        pipeline: DrawPipeline::Gradient(gradient_offset as u32),
+                        blend_mode: renderable.blend_mode,
+                        clip: renderable.clip,
+                        stencil,
+                        viewport: renderable.viewport.rect,
+                        geometry: self.push_geometry(&mesh.vertices, &mesh.indices),
+                    });
+                }
+            }
+        }
+
+        for buffer in [
+            &mut self.uniforms,
+            &mut self.gradients,
+            &mut self.vertices,
+            &mut self.indices,
+        ]
+        .iter_mut()
+        {
+            let generation = buffer.generation();
+            buffer.flush(&self.instance);
+
+            if buffer.generation() == generation {
+                stats.buffers_reused += 1;
+            } else {
+                stats.buffers_created += 1;
+            }
+
+            stats.uploaded_bytes += buffer.len();
+            stats.buffer_capacity += buffer.capacity();
+        }
+
+        if self.uniforms.generation() != self.uniform_generation {
+            self.uniform_bind_group = uniform_bind_group(
+                &self.instance,
+                "primary uniforms",
+                &self.pipelines.primary_uniforms,
+                &self.uniforms,
+                PRIMARY_UNIFORMS_SIZE,
+            );
+            self.uniform_generation = self.uniforms.generation();
+        }
+
+        if self.gradients.generation() != self.gradient_generation {
+            self.gradient_bind_group = uniform_bind_group(
+                &self.instance,
+                "gradient uniforms",
+                &self.pipelines.gradient_uniforms,
+                &self.gradients,
+                GRADIENT_UNIFORMS_SIZE,
+            );
+            self.gradient_generation = self.gradients.generation();
+        }
+
+        stats.upload = upload_start.elapsed();
+        let encoding_start = Instant::now();
+
         let mut encoder =
             self.instance
                 .device
@@ -264,175 +1410,402 @@ impl Renderer {
                     label: Some("render encoder"),
                 });
 
-        let mut primary_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("primary pass"),
-            color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: &self.textures.primary_image,
-                resolve_target: Some(&swapchain_frame.output.view),
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: frame.clear_color[0] as f64,
-                        g: frame.clear_color[1] as f64,
-                        b: frame.clear_color[2] as f64,
-                        a: frame.clear_color[3] as f64,
-                    }),
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.textures.primary_depth,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: true,
-                }),
-                stencil_ops: None,
-            }),
-        });
+        let output = &swapchain_frame.output.view;
+        let format = self.swapchain.format();
+        let width = self.swapchain.width();
+        let height = self.swapchain.height();
 
-        primary_pass.set_pipeline(&self.pipelines.primary);
+        let schedule = self.graph.prepare(
+            &self.instance,
+            format,
+            width,
+            height,
+            |node, builder| match node {
+                BuiltinNode::Primary => {
+                    builder
+                        .create_texture(
+                            RenderGraph::SCENE,
+                            TextureDesc::default().sample_count(SAMPLE_COUNT),
+                        )
+                        .create_texture(
+                            RenderGraph::DEPTH,
+                            TextureDesc::default()
+                                .format(DEPTH_FORMAT)
+                                .sample_count(SAMPLE_COUNT),
+                        )
+                        // resolved straight into the output when nothing reads the scene
+                        .write(RenderGraph::OUTPUT);
+                }
+                BuiltinNode::Post => {
+                    if post_processing {
+                        builder
+                            .read(RenderGraph::SCENE)
+                            .create_texture(POST_TARGETS[0], TextureDesc::default())
+                            .create_texture(POST_TARGETS[1], TextureDesc::default())
+                            .write(RenderGraph::OUTPUT);
+                    }
+                }
+            },
+        );
 
-        let mut idx = 0;
+        let scene_read = self.graph.is_read(RenderGraph::SCENE);
 
-        for renderable in &frame.renderables {
-            match renderable {
-                Renderable::Ui {
-                    mesh,
-                    transform,
-                    camera,
-                } => {
-                    if let Some(data) = self.primary_uniforms.get_mut(idx) {
-                        self.instance.queue.write_buffer(
-                            &data.uniform_buffer,
-                            0,
-                            bytes_of(transform),
-                        );
-                        self.instance.queue.write_buffer(
-                            &data.uniform_buffer,
-                            64,
-                            bytes_of(camera),
-                        );
+        for index in schedule {
+            match self.graph.builtin(index) {
+                Some(BuiltinNode::Primary) => {
+                    let (scene, resolve) = self.graph.attachment(RenderGraph::SCENE, output);
+                    let (depth, _) = self.graph.attachment(RenderGraph::DEPTH, output);
+
+                    let mut primary_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("primary pass"),
+                        color_attachments: &[wgpu::RenderPassColorAttachment {
+                            view: scene,
+                            resolve_target: Some(resolve.unwrap_or(output)),
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: frame.clear_color[0] as f64,
+                                    g: frame.clear_color[1] as f64,
+                                    b: frame.clear_color[2] as f64,
+                                    a: frame.clear_color[3] as f64,
+                                }),
+                                store: true,
+                            },
+                        }],
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: depth,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: true,
+                            }),
+                            stencil_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(0),
+                                store: true,
+                            }),
+                        }),
+                    });
+
+                    let mut current_pipeline = None;
+                    let mut current_scissor = None;
+                    let mut current_stencil = 0;
+                    let mut current_viewport = None;
+
+                    for draw in std::mem::take(&mut draws) {
+                        let clip = match draw.clip {
+                            Some(clip) => clip.intersect(&draw.viewport),
+                            None => draw.viewport,
+                        };
+
+                        let scissor = match scissor_rect(clip, width, height) {
+                            Some(scissor) => scissor,
+                            // clipped away entirely
+                            None => continue,
+                        };
 
-                        let index_data: &[u8] = cast_slice(&mesh.indices);
-
-                        if data.index_buffer_size == index_data.len() {
-                            self.instance
-                                .queue
-                                .write_buffer(&data.index_buffer, 0, index_data);
-                        } else {
-                            let index_buffer = self.instance.device.create_buffer_init(
-                                &wgpu::util::BufferInitDescriptor {
-                                    label: Some("primary index buffer"),
-                                    contents: index_data,
-                                    usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::INDEX,
-                                },
+                        if current_viewport != Some(draw.viewport) {
+                            let viewport = draw.viewport;
+                            primary_pass.set_viewport(
+                                viewport.min.x,
+                                viewport.min.y,
+                                viewport.width(),
+                                viewport.height(),
+                                0.0,
+                                1.0,
                             );
 
-                            data.index_buffer = index_buffer;
-                            data.index_buffer_size = index_data.len();
-                            data.indices = mesh.indices.len() as u32;
+                            current_viewport = Some(viewport);
                         }
 
-                        let vertex_data: &[u8] = cast_slice(&mesh.vertices);
-
-                        if data.vertex_buffer_size == vertex_data.len() {
-                            self.instance
-                                .queue
-                                .write_buffer(&data.vertex_buffer, 0, vertex_data);
-                        } else {
-                            let vertex_buffer = self.instance.device.create_buffer_init(
-                                &wgpu::util::BufferInitDescriptor {
-                                    label: Some("primary vertex buffer"),
-                                    contents: vertex_data,
-                                    usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::VERTEX,
-                                },
-                            );
+                        if current_scissor != Some(scissor) {
+                            let (x, y, width, height) = scissor;
+                            primary_pass.set_scissor_rect(x, y, width, height);
 
-                            data.vertex_buffer = vertex_buffer;
-                            data.vertex_buffer_size = vertex_data.len();
+                            current_scissor = Some(scissor);
                         }
-                    } else {
-                        let uniform_buffer = self.instance.device.create_buffer_init(
-                            &wgpu::util::BufferInitDescriptor {
-                                label: Some("primary uniforms"),
-                                contents: cast_slice(&[*transform, *camera]),
-                                usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::UNIFORM,
-                            },
-                        );
 
-                        let uniform_bind_group =
-                            self.instance
-                                .device
-                                .create_bind_group(&wgpu::BindGroupDescriptor {
-                                    label: Some("primary uniforms"),
-                                    layout: &self.pipelines.primary_uniforms,
-                                    entries: &[wgpu::BindGroupEntry {
-                                        binding: 0,
-                                        resource: uniform_buffer.as_entire_binding(),
-                                    }],
-                                });
-
-                        let vertex_data = cast_slice(&mesh.vertices);
-
-                        let vertex_buffer = self.instance.device.create_buffer_init(
-                            &wgpu::util::BufferInitDescriptor {
-                                label: Some("primary vertex buffer"),
-                                contents: vertex_data,
-                                usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::VERTEX,
-                            },
-                        );
+                        if current_stencil != draw.stencil {
+                            primary_pass.set_stencil_reference(draw.stencil);
+
+                            current_stencil = draw.stencil;
+                        }
 
-                        let index_data = cast_slice(&mesh.indices);
+                        if current_pipeline != Some((draw.pipeline, draw.blend_mode)) {
+                            let blend_index = draw.blend_mode as usize;
 
-                        let index_buffer = self.instance.device.create_buffer_init(
-                            &wgpu::util::BufferInitDescriptor {
-                                label: Some("primary index buffer"),
-                                contents: index_data,
-                                usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::INDEX,
-                            },
+                            match draw.pipeline {
+                                DrawPipeline::Primary => {
+                                    primary_pass.set_pipeline(&self.pipelines.primary[blend_index])
+                                }
+                                DrawPipeline::Sprite(key) => {
+                                    primary_pass.set_pipeline(&self.pipelines.sprite[blend_index]);
+                                    primary_pass.set_bind_group(
+                                        1,
+                                        self.texture_bind_groups.bind_group(&key),
+                                        &[],
+                                    );
+                                }
+                                DrawPipeline::Material(key) => {
+                                    primary_pass
+                                        .set_pipeline(self.material_pipelines.pipeline(&key));
+                                    primary_pass.set_bind_group(
+                                        1,
+                                        materials[&key.0].bind_group(),
+                                        &[],
+                                    );
+                                }
+                                DrawPipeline::Gradient(offset) => {
+                                    primary_pass
+                                        .set_pipeline(&self.pipelines.gradient[blend_index]);
+                                    primary_pass.set_bind_group(
+                                        1,
+                                        &self.gradient_bind_group,
+                                        &[offset],
+                                    );
+                                }
+                                DrawPipeline::Mask(true) => {
+                                    primary_pass.set_pipeline(&self.pipelines.mask_increment)
+                                }
+                                DrawPipeline::Mask(false) => {
+                                    primary_pass.set_pipeline(&self.pipelines.mask_decrement)
+                                }
+                            }
+
+                            current_pipeline = Some((draw.pipeline, draw.blend_mode));
+                        }
+
+                        primary_pass.set_bind_group(
+                            0,
+                            &self.uniform_bind_group,
+                            &[draw.uniform_offset],
                         );
 
-                        self.primary_uniforms.push(PrimaryData {
-                            uniform_buffer,
-                            uniform_bind_group,
-                            vertex_buffer,
-                            vertex_buffer_size: vertex_data.len(),
-                            index_buffer,
-                            index_buffer_size: index_data.len(),
-                            indices: mesh.indices.len() as u32,
-                        });
+                        match draw.geometry {
+                            DrawGeometry::Shared {
+                                vertices,
+                                indices,
+                                vertex_count,
+                                count,
+                            } => {
+                                // empty slices would bind the rest of the buffer
+                                if count == 0 {
+                                    continue;
+                                }
+
+                                primary_pass
+                                    .set_vertex_buffer(0, self.vertices.buffer().slice(vertices));
+                                primary_pass.set_index_buffer(
+                                    self.indices.buffer().slice(indices),
+                                    wgpu::IndexFormat::Uint32,
+                                );
+
+                                primary_pass.draw_indexed(0..count, 0, 0..1);
+
+                                stats.draw_calls += 1;
+                                stats.vertices += vertex_count as u64;
+                                stats.indices += count as u64;
+                            }
+                            DrawGeometry::Handle(mesh) => {
+                                primary_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                                primary_pass.set_index_buffer(
+                                    mesh.index_buffer.slice(..),
+                                    wgpu::IndexFormat::Uint32,
+                                );
+
+                                primary_pass.draw_indexed(0..mesh.indices, 0, 0..1);
+
+                                stats.draw_calls += 1;
+                                stats.vertices += mesh.vertices as u64;
+                                stats.indices += mesh.indices as u64;
+                            }
+                        }
                     }
 
-                    idx += 1;
+                    drop(primary_pass);
+
+                    // other nodes read the scene but post-processing won't copy it out
+                    if scene_read && !post_processing {
+                        let scene = self.graph.texture(RenderGraph::SCENE, output);
+
+                        apply_effects(
+                            &self.instance,
+                            &mut encoder,
+                            std::slice::from_mut(&mut self.copy_effect),
+                            [scene, scene, scene],
+                            &self.post_sampler,
+                            format,
+                            output,
+                        );
+                    }
                 }
+                Some(BuiltinNode::Post) => {
+                    if post_processing {
+                        apply_effects(
+                            &self.instance,
+                            &mut encoder,
+                            &mut self.effects,
+                            [
+                                self.graph.texture(RenderGraph::SCENE, output),
+                                self.graph.texture(POST_TARGETS[0], output),
+                                self.graph.texture(POST_TARGETS[1], output),
+                            ],
+                            &self.post_sampler,
+                            format,
+                            output,
+                        );
+                    }
+                }
+                None => self.graph.run_custom(
+                    index,
+                    &self.instance,
+                    &mut encoder,
+                    output,
+                    format,
+                    width,
+                    height,
+                ),
             }
         }
 
-        idx = 0;
+        self.instance
+            .queue
+            .submit(std::iter::once(encoder.finish()));
 
-        for renderable in &frame.renderables {
-            match renderable {
-                Renderable::Ui { .. } => {
-                    let data = &self.primary_uniforms[idx];
+        stats.encoding = encoding_start.elapsed();
 
-                    primary_pass.set_bind_group(0, &data.uniform_bind_group, &[]);
-                    primary_pass.set_vertex_buffer(0, data.vertex_buffer.slice(..));
-                    primary_pass
-                        .set_index_buffer(data.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.texture_bind_groups
+            .end_frame(self.uniforms.trim_frames);
+        self.material_pipelines.end_frame(self.uniforms.trim_frames);
+        self.glyph_cache.end_frame(self.uniforms.trim_frames);
 
-                    primary_pass.draw_indexed(0..data.indices, 0, 0..1);
+        self.last_stats = Some(stats);
 
-                    idx += 1;
+        Ok(stats)
+    }
+
+    /// Pushes the draws changing the mask level from the masks in `from` to the ones in
+    /// `to`, both outermost first.
+    #[inline]
+    fn change_masks(
+        &mut self,
+        frame_masks: &[Mask<'_>],
+        from: &[usize],
+        to: &[usize],
+        masks: &mut MaskDraws,
+        draws: &mut Vec<Draw<'_>>,
+    ) {
+        let common = from
+            .iter()
+            .zip(to)
+            .take_while(|(from, to)| from == to)
+            .count();
+
+        let target = Viewport::new(self.swapchain.width(), self.swapchain.height()).rect;
+
+        // every pixel at a level belongs to the innermost mask, so popping lowers all of them
+        for level in (common..from.len()).rev() {
+            let (uniform_offset, geometry) = self.screen_geometry(masks);
+            draws.push(mask_draw(
+                uniform_offset,
+                geometry,
+                false,
+                level as u32 + 1,
+                target,
+            ));
+        }
+
+        for (level, &mask) in to.iter().enumerate().skip(common) {
+            let level = level as u32;
+            let (uniform_offset, geometry) = self.mask_geometry(frame_masks, mask, masks);
+            let viewport = frame_masks[mask].viewport.rect;
+
+            match frame_masks[mask].mode {
+                MaskMode::Inside => {
+                    draws.push(mask_draw(uniform_offset, geometry, true, level, viewport));
+                }
+                MaskMode::Outside => {
+                    let (screen_offset, screen) = self.screen_geometry(masks);
+                    draws.push(mask_draw(screen_offset, screen, true, level, target));
+                    draws.push(mask_draw(
+                        uniform_offset,
+                        geometry,
+                        false,
+                        level + 1,
+                        viewport,
+                    ));
                 }
             }
         }
+    }
 
-        drop(primary_pass);
+    /// Uniforms and geometry of `mask`, pushed the first time it's drawn.
+    #[inline]
+    fn mask_geometry(
+        &mut self,
+        frame_masks: &[Mask<'_>],
+        mask: usize,
+        masks: &mut MaskDraws,
+    ) -> (u32, DrawGeometry<'static>) {
+        if let Some(draw) = masks.masks.get(&mask) {
+            return draw.clone();
+        }
 
-        self.instance
-            .queue
-            .submit(std::iter::once(encoder.finish()));
+        let Mask {
+            ref mesh,
+            transform,
+            camera,
+            ..
+        } = frame_masks[mask];
+
+        let uniform_offset = self.uniforms.push(
+            cast_slice(&[transform, camera]),
+            wgpu::BIND_BUFFER_ALIGNMENT,
+        ) as u32;
+        let geometry = self.push_geometry(&mesh.vertices, &mesh.indices);
+
+        masks.masks.insert(mask, (uniform_offset, geometry.clone()));
+
+        (uniform_offset, geometry)
+    }
+
+    /// Uniforms and geometry of a quad covering the whole target.
+    #[inline]
+    fn screen_geometry(&mut self, masks: &mut MaskDraws) -> (u32, DrawGeometry<'static>) {
+        if let Some(ref draw) = masks.screen {
+            return draw.clone();
+        }
+
+        let uniform_offset = self.uniforms.push(
+            cast_slice(&[Mat4::IDENTITY, Mat4::IDENTITY]),
+            wgpu::BIND_BUFFER_ALIGNMENT,
+        ) as u32;
+
+        let vertices: [[f32; 7]; 4] = [
+            [-1.0, -1.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            [1.0, -1.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            [1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+            [-1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+        ];
+        let geometry = self.push_geometry(&vertices, &[0, 1, 2, 0, 2, 3]);
 
-        Ok(())
+        masks.screen = Some((uniform_offset, geometry.clone()));
+
+        (uniform_offset, geometry)
+    }
+
+    /// Pushes geometry into the shared per-frame buffers.
+    #[inline]
+    fn push_geometry<T: Pod>(&mut self, vertices: &[T], indices: &[u32]) -> DrawGeometry<'static> {
+        let vertex_data: &[u8] = cast_slice(vertices);
+        let index_data: &[u8] = cast_slice(indices);
+
+        let vertex_offset = self.vertices.push(vertex_data, 4);
+        let index_offset = self.indices.push(index_data, 4);
+
+        DrawGeometry::Shared {
+            vertices: vertex_offset..vertex_offset + vertex_data.len() as u64,
+            indices: index_offset..index_offset + index_data.len() as u64,
+            vertex_count: vertices.len() as u32,
+            count: indices.len() as u32,
+        }
     }
 
     #[inline]
@@ -440,10 +1813,19 @@ impl Renderer {
         self.swapchain.desc.width as f32 / self.swapchain.desc.height as f32
     }
 
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.swapchain.width()
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.swapchain.height()
+    }
+
     #[inline]
     pub fn resize(&mut self, width: u32, height: u32) {
         self.swapchain.resize(&self.instance, width, height);
-        self.textures = RenderTextures::new(&self.instance, self.swapchain.format(), width, height);
     }
 
     #[inline]
@@ -451,3 +1833,152 @@ impl Renderer {
         self.swapchain.recreate(&self.instance);
     }
 }
+
+/// Mask draws already pushed this frame.
+#[derive(Default)]
+struct MaskDraws {
+    masks: HashMap<usize, (u32, DrawGeometry<'static>)>,
+    screen: Option<(u32, DrawGeometry<'static>)>,
+}
+
+/// A draw raising or lowering the mask level of the pixels at `stencil` it covers.
+#[inline]
+fn mask_draw<'a>(
+    uniform_offset: u32,
+    geometry: DrawGeometry<'a>,
+    increment: bool,
+    stencil: u32,
+    viewport: Rect,
+) -> Draw<'a> {
+    Draw {
+        uniform_offset,
+        pipeline: DrawPipeline::Mask(increment),
+        blend_mode: BlendMode::Normal,
+        // masks cover pixels outside the current clip rect too
+        clip: None,
+        stencil,
+        viewport,
+        geometry,
+    }
+}
+
+/// Indices of `mask` and the masks enclosing it, outermost first.
+#[inline]
+fn mask_chain(masks: &[Mask<'_>], mut mask: Option<usize>) -> Vec<usize> {
+    let mut chain = Vec::new();
+
+    while let Some(i) = mask {
+        chain.push(i);
+        mask = masks[i].parent;
+    }
+
+    chain.reverse();
+    chain
+}
+
+/// Indices of the visible `renderables` in the order they're painted, by layer,
+/// z-index, distance from the camera, farthest first, and submission.
+#[inline]
+fn draw_order(renderables: &[Renderable<'_>], hidden_layers: &HashSet<Layer>) -> Vec<usize> {
+    let depth = |renderable: &Renderable<'_>| {
+        let origin = renderable.camera * renderable.transform * Vec4::W;
+        origin.z / origin.w
+    };
+
+    let mut order: Vec<_> = (0..renderables.len())
+        .filter(|&i| !hidden_layers.contains(&renderables[i].layer))
+        .collect();
+
+    // stable, so submission order breaks ties
+    order.sort_by(|&a, &b| {
+        let a = &renderables[a];
+        let b = &renderables[b];
+
+        a.layer
+            .cmp(&b.layer)
+            .then(a.z_index.cmp(&b.z_index))
+            .then(depth(b).total_cmp(&depth(a)))
+    });
+
+    order
+}
+
+/// Largest number of pixels one local unit covers around the origin when drawn with
+/// `matrix`, the product of view projection and transform.
+///
+/// Only the scale at the origin is measured, parts of a shape further from or closer to
+/// a perspective camera than its origin get the same resolution.
+#[inline]
+fn screen_scale(matrix: Mat4, target_size: Vec2) -> f32 {
+    // perspective shrinks everything by the clip space w of the origin, a tiny w is
+    // bounded by `Frame::min_resolution`
+    let w = matrix.w_axis.w.abs().max(f32::EPSILON);
+
+    let x_axis = matrix.x_axis.truncate().truncate() * target_size / 2.0;
+    let y_axis = matrix.y_axis.truncate().truncate() * target_size / 2.0;
+
+    x_axis.length().max(y_axis.length()) / w
+}
+
+/// Maps clip space depth into the `index`th of `count` slices of the depth range, later
+/// slices are closer, so depth testing follows the painting order.
+#[inline]
+fn depth_slice(index: usize, count: usize) -> Mat4 {
+    let scale = 1.0 / count as f32;
+    let offset = (count - 1 - index) as f32 * scale;
+
+    Mat4::from_translation(Vec3::new(0.0, 0.0, offset))
+        * Mat4::from_scale(Vec3::new(1.0, 1.0, scale))
+}
+
+/// Converts a clip rect in pixels to a scissor rect inside the target, [`None`] if
+/// nothing is left.
+#[inline]
+fn scissor_rect(clip: Rect, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let target = Rect::new(Vec2::ZERO, Vec2::new(width as f32, height as f32));
+    let clip = clip.intersect(&target);
+
+    let min_x = clip.min.x.floor() as u32;
+    let min_y = clip.min.y.floor() as u32;
+    let max_x = (clip.max.x.ceil() as u32).min(width);
+    let max_y = (clip.max.y.ceil() as u32).min(height);
+
+    if max_x <= min_x || max_y <= min_y {
+        return None;
+    }
+
+    Some((min_x, min_y, max_x - min_x, max_y - min_y))
+}
+
+/// Scratch targets of post-processing effects.
+const POST_TARGETS: [&str; 2] = ["post_0", "post_1"];
+
+/// Multisample count of the primary pass.
+const SAMPLE_COUNT: u32 = 8;
+
+/// Size of the uniforms of a single draw.
+const PRIMARY_UNIFORMS_SIZE: u64 = 128;
+
+#[inline]
+fn uniform_bind_group(
+    instance: &RenderInstance,
+    label: &str,
+    layout: &wgpu::BindGroupLayout,
+    uniforms: &UploadBuffer,
+    size: u64,
+) -> wgpu::BindGroup {
+    instance
+        .device
+        .create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: uniforms.buffer(),
+                    offset: 0,
+                    size: wgpu::BufferSize::new(size),
+                }),
+            }],
+        })
+}
@@ -0,0 +1,95 @@
+struct VertexInput {
+	[[location(0)]] position: vec3<f32>;
+	[[location(1)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+	[[builtin(position)]] position: vec4<f32>;
+	[[location(0)]] local: vec2<f32>;
+};
+
+[[block]]
+struct Uniforms {
+	transform: mat4x4<f32>;	
+	view_proj: mat4x4<f32>;
+};
+
+[[block]]
+struct Gradient {
+	kind: u32;
+	spread: u32;
+	count: u32;
+	geometry: vec4<f32>;
+	offsets: array<vec4<f32>, 16>;
+	colors: array<vec4<f32>, 16>;
+};
+
+[[group(0), binding(0)]]
+var<uniform> uniforms: Uniforms;
+
+[[group(1), binding(0)]]
+var<uniform> gradient: Gradient;
+
+[[stage(vertex)]]
+fn main(in: VertexInput) -> VertexOutput {
+	var out: VertexOutput;
+
+	out.position = uniforms.view_proj * uniforms.transform * vec4<f32>(in.position, 1.0);
+	out.local = in.position.xy;
+
+	return out;
+}
+
+[[stage(fragment)]]
+fn main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+	let geometry = gradient.geometry;
+	var t: f32;
+
+	if (gradient.kind == 0u) {
+		// linear
+		let direction = geometry.zw - geometry.xy;
+		t = dot(in.local - geometry.xy, direction) / max(dot(direction, direction), 0.000001);
+	} else {
+		if (gradient.kind == 1u) {
+			// radial
+			t = distance(in.local, geometry.xy) / max(geometry.z, 0.000001);
+		} else {
+			// conic
+			let offset = in.local - geometry.xy;
+			t = fract((atan2(offset.y, offset.x) - geometry.z) / 6.283185307);
+		}
+	}
+
+	if (gradient.spread == 1u) {
+		// repeat
+		t = fract(t);
+	} else {
+		if (gradient.spread == 2u) {
+			// reflect
+			t = 1.0 - abs(fract(t * 0.5) * 2.0 - 1.0);
+		} else {
+			t = clamp(t, 0.0, 1.0);
+		}
+	}
+
+	var color: vec4<f32> = gradient.colors[0];
+	var i: u32 = 1u;
+
+	loop {
+		if (i >= gradient.count) {
+			break;
+		}
+
+		let start = gradient.offsets[i - 1u].x;
+		let end = gradient.offsets[i].x;
+
+		if (t > start) {
+			let f = clamp((t - start) / max(end - start, 0.000001), 0.0, 1.0);
+			color = mix(gradient.colors[i - 1u], gradient.colors[i], vec4<f32>(f, f, f, f));
+		}
+
+		i = i + 1u;
+	}
+
+	return output(color);
+}
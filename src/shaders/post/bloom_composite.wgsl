@@ -0,0 +1,25 @@
+[[block]]
+struct Params {
+	threshold: f32;
+	intensity: f32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var source_sampler: sampler;
+
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+[[group(0), binding(3)]]
+var effect_input: texture_2d<f32>;
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+	let color = textureSample(effect_input, source_sampler, uv);
+	let bloom = textureSample(source, source_sampler, uv);
+
+	return vec4<f32>(color.rgb + bloom.rgb * params.intensity, color.a);
+}
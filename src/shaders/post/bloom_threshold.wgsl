@@ -0,0 +1,25 @@
+[[block]]
+struct Params {
+	threshold: f32;
+	intensity: f32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var source_sampler: sampler;
+
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+	let color = textureSample(source, source_sampler, uv);
+	let brightness = max(max(color.r, color.g), color.b);
+
+	// keep the part of the color above the threshold
+	let factor = max(brightness - params.threshold, 0.0) / max(brightness, 0.0001);
+
+	return vec4<f32>(color.rgb * factor, 1.0);
+}
@@ -0,0 +1,28 @@
+[[block]]
+struct Params {
+	direction: vec2<f32>;
+	radius: f32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var source_sampler: sampler;
+
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+	let texel = params.direction * params.radius / vec2<f32>(textureDimensions(source));
+
+	// 9 tap gaussian, sampled between texels to use linear filtering
+	var color = textureSample(source, source_sampler, uv) * 0.2270270270;
+	color = color + textureSample(source, source_sampler, uv + texel * 1.3846153846) * 0.3162162162;
+	color = color + textureSample(source, source_sampler, uv - texel * 1.3846153846) * 0.3162162162;
+	color = color + textureSample(source, source_sampler, uv + texel * 3.2307692308) * 0.0702702703;
+	color = color + textureSample(source, source_sampler, uv - texel * 3.2307692308) * 0.0702702703;
+
+	return color;
+}
@@ -0,0 +1,26 @@
+[[block]]
+struct Params {
+	amount: f32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var source_sampler: sampler;
+
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+	// channels are split further apart towards the edges
+	let offset = (uv - vec2<f32>(0.5, 0.5)) * 2.0 * params.amount
+		/ vec2<f32>(textureDimensions(source));
+
+	let color = textureSample(source, source_sampler, uv);
+	let r = textureSample(source, source_sampler, uv + offset).r;
+	let b = textureSample(source, source_sampler, uv - offset).b;
+
+	return vec4<f32>(r, color.g, b, color.a);
+}
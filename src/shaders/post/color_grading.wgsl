@@ -0,0 +1,51 @@
+[[block]]
+struct Params {
+	size: f32;
+	intensity: f32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var source_sampler: sampler;
+
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+[[group(0), binding(4)]]
+var lut: texture_2d<f32>;
+
+fn linear_to_srgb(c: f32) -> f32 {
+	if (c <= 0.0031308) {
+		return c * 12.92;
+	}
+
+	return 1.055 * pow(c, 1.0 / 2.4) - 0.055;
+}
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+	let color = textureSample(source, source_sampler, uv);
+	let linear = clamp(color.rgb, vec3<f32>(0.0, 0.0, 0.0), vec3<f32>(1.0, 1.0, 1.0));
+
+	// the scene is linear but the lut is indexed by srgb colors, sampling the lut
+	// decodes its srgb texels back to linear
+	let c = vec3<f32>(linear_to_srgb(linear.r), linear_to_srgb(linear.g), linear_to_srgb(linear.b));
+
+	// the lut is a strip of `size` slices of `size` by `size` texels, one per blue value
+	let blue = c.b * (params.size - 1.0);
+	let slice = floor(blue);
+	let next_slice = min(slice + 1.0, params.size - 1.0);
+
+	let x = (c.r * (params.size - 1.0) + 0.5) / (params.size * params.size);
+	let y = (c.g * (params.size - 1.0) + 0.5) / params.size;
+
+	let a = textureSample(lut, source_sampler, vec2<f32>(x + slice / params.size, y));
+	let b = textureSample(lut, source_sampler, vec2<f32>(x + next_slice / params.size, y));
+	let t = blue - slice;
+	let graded = mix(a.rgb, b.rgb, vec3<f32>(t, t, t));
+
+	let intensity = params.intensity;
+	return vec4<f32>(mix(color.rgb, graded, vec3<f32>(intensity, intensity, intensity)), color.a);
+}
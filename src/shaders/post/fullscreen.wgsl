@@ -0,0 +1,17 @@
+struct VertexOutput {
+	[[builtin(position)]] position: vec4<f32>;
+	[[location(0)]] uv: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+	var out: VertexOutput;
+
+	// a single triangle covering the screen
+	let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+	out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+	out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+
+	return out;
+}
@@ -0,0 +1,25 @@
+[[block]]
+struct Params {
+	strength: f32;
+	radius: f32;
+	softness: f32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d<f32>;
+
+[[group(0), binding(1)]]
+var source_sampler: sampler;
+
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+	let color = textureSample(source, source_sampler, uv);
+
+	let t = clamp((distance(uv, vec2<f32>(0.5, 0.5)) - params.radius) / params.softness, 0.0, 1.0);
+	let darken = t * t * (3.0 - 2.0 * t) * params.strength;
+
+	return vec4<f32>(color.rgb * (1.0 - darken), color.a);
+}
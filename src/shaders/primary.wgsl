@@ -29,5 +29,5 @@ fn main(in: VertexInput) -> VertexOutput {
 
 [[stage(fragment)]]
 fn main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
-	return in.color;
+	return output(in.color);
 }
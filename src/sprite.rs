@@ -0,0 +1,100 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec2, Vec3};
+
+use crate::{Rect, Sampler};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct SpriteVertex {
+    pub position: Vec3,
+    pub uv: Vec2,
+    pub color: [f32; 4],
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteOptions {
+    /// Multiplied with the texture color.
+    pub tint: [f32; 4],
+    /// Part of the texture to draw in uv coordinates, see [`Texture::uv_rect`](crate::Texture::uv_rect).
+    pub source: Rect,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub sampler: Sampler,
+}
+
+impl Default for SpriteOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            tint: [1.0; 4],
+            source: Rect::UNIT,
+            flip_x: false,
+            flip_y: false,
+            sampler: Sampler::default(),
+        }
+    }
+}
+
+impl SpriteOptions {
+    #[inline]
+    pub fn tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    #[inline]
+    pub fn source(mut self, source: Rect) -> Self {
+        self.source = source;
+        self
+    }
+
+    #[inline]
+    pub fn flip_x(mut self, flip_x: bool) -> Self {
+        self.flip_x = flip_x;
+        self
+    }
+
+    #[inline]
+    pub fn flip_y(mut self, flip_y: bool) -> Self {
+        self.flip_y = flip_y;
+        self
+    }
+
+    #[inline]
+    pub fn sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = sampler;
+        self
+    }
+}
+
+pub(crate) const SPRITE_INDICES: [u32; 6] = [0, 1, 2, 2, 3, 0];
+
+/// Generates the corners of a sprite covering `rect`, counter clockwise starting in the
+/// bottom left.
+#[inline]
+pub(crate) fn sprite_vertices(rect: &Rect, options: &SpriteOptions) -> [SpriteVertex; 4] {
+    let (mut u0, mut u1) = (options.source.min.x, options.source.max.x);
+    // uv coordinates go down, world coordinates go up
+    let (mut v0, mut v1) = (options.source.max.y, options.source.min.y);
+
+    if options.flip_x {
+        std::mem::swap(&mut u0, &mut u1);
+    }
+
+    if options.flip_y {
+        std::mem::swap(&mut v0, &mut v1);
+    }
+
+    let vertex = |x: f32, y: f32, u: f32, v: f32| SpriteVertex {
+        position: Vec3::new(x, y, 0.0),
+        uv: Vec2::new(u, v),
+        color: options.tint,
+    };
+
+    [
+        vertex(rect.min.x, rect.min.y, u0, v0),
+        vertex(rect.max.x, rect.min.y, u1, v0),
+        vertex(rect.max.x, rect.max.y, u1, v1),
+        vertex(rect.min.x, rect.max.y, u0, v1),
+    ]
+}
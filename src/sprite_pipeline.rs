@@ -0,0 +1,106 @@
+use crate::{primary_pipeline::depth_stencil_state, BlendMode, RenderInstance};
+
+/// Layout of the texture and sampler of a sprite.
+pub fn sprite_texture(instance: &RenderInstance) -> wgpu::BindGroupLayout {
+    instance
+        .device
+        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sprite texture"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    ty: wgpu::BindingType::Sampler {
+                        filtering: true,
+                        comparison: false,
+                    },
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    count: None,
+                },
+            ],
+        })
+}
+
+pub fn sprite_pipeline(
+    instance: &RenderInstance,
+    format: wgpu::TextureFormat,
+    uniforms: &wgpu::BindGroupLayout,
+    texture: &wgpu::BindGroupLayout,
+    blend_mode: BlendMode,
+) -> wgpu::RenderPipeline {
+    let module = instance
+        .device
+        .create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("sprite shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                blend_mode
+                    .shader_source(include_str!("shaders/sprite.wgsl"))
+                    .into(),
+            ),
+            flags: wgpu::ShaderFlags::all(),
+        });
+
+    let layout = instance
+        .device
+        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sprite layout"),
+            bind_group_layouts: &[uniforms, texture],
+            push_constant_ranges: &[],
+        });
+
+    instance
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("sprite pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: 12 + 8 + 16,
+                    step_mode: wgpu::InputStepMode::Vertex,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x3,
+                            shader_location: 0,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 12,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 1,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: 12 + 8,
+                            format: wgpu::VertexFormat::Float32x4,
+                            shader_location: 2,
+                        },
+                    ],
+                }],
+                entry_point: "main",
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: blend_mode.blend_state(),
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+                entry_point: "main",
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: wgpu::MultisampleState {
+                count: 8,
+                ..Default::default()
+            },
+            depth_stencil: Some(depth_stencil_state()),
+        })
+}
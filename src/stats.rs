@@ -0,0 +1,54 @@
+use std::{fmt, time::Duration};
+
+/// What went into rendering a frame, returned by
+/// [`Renderer::render`](crate::Renderer::render) and kept for the next frame in
+/// [`Frame::last_stats`](crate::Frame::last_stats).
+///
+/// Draw them on screen with [`Frame::draw_stats`](crate::Frame::draw_stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// Draw calls of the primary pass, including mask shapes.
+    pub draw_calls: u32,
+    pub vertices: u64,
+    pub indices: u64,
+    /// Bytes written to the per-frame buffers with `write_buffer`.
+    pub uploaded_bytes: u64,
+    /// Per-frame buffers recreated to fit this frame.
+    pub buffers_created: u32,
+    /// Per-frame buffers kept from the last frame.
+    pub buffers_reused: u32,
+    /// Total size of the per-frame buffers in bytes.
+    pub buffer_capacity: u64,
+    /// Renderables skipped for being outside the view.
+    pub culled: usize,
+    /// Time spent generating meshes of shapes while building the frame.
+    pub tessellation: Duration,
+    /// Time spent packing and uploading uniforms and geometry.
+    pub upload: Duration,
+    /// Time spent recording and submitting commands.
+    pub encoding: Duration,
+}
+
+impl fmt::Display for RenderStats {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "draw calls: {}, culled: {}",
+            self.draw_calls, self.culled
+        )?;
+        writeln!(f, "vertices: {}, indices: {}", self.vertices, self.indices)?;
+        writeln!(
+            f,
+            "uploaded: {} B, buffers: {} B ({} created, {} reused)",
+            self.uploaded_bytes, self.buffer_capacity, self.buffers_created, self.buffers_reused
+        )?;
+        write!(
+            f,
+            "tessellation: {:.2} ms, upload: {:.2} ms, encoding: {:.2} ms",
+            self.tessellation.as_secs_f64() * 1000.0,
+            self.upload.as_secs_f64() * 1000.0,
+            self.encoding.as_secs_f64() * 1000.0,
+        )
+    }
+}
@@ -0,0 +1,173 @@
+use ab_glyph::GlyphId;
+use glam::{Mat4, Vec2};
+
+use crate::{
+    sprite::{sprite_vertices, SPRITE_INDICES},
+    CachedGlyph, GlyphCache, Rect, RenderInstance, SpriteOptions, SpriteVertex, TextDecorationKind,
+    TextLayout,
+};
+
+/// Glyph quads of a piece of text that share an atlas page.
+#[derive(Debug, Default)]
+pub(crate) struct TextBatch {
+    pub page: usize,
+    pub vertices: Vec<SpriteVertex>,
+    pub indices: Vec<u32>,
+}
+
+/// Largest pixel size glyphs are rasterized at, larger text is scaled up.
+const MAX_PIXEL_SIZE: f32 = 256.0;
+
+/// Number of pixels one local unit covers on screen when drawn with `matrix`, the
+/// product of view projection and transform.
+#[inline]
+pub(crate) fn pixels_per_unit(matrix: Mat4, target_size: Vec2) -> f32 {
+    let y_axis = Vec2::new(matrix.y_axis.x, matrix.y_axis.y) * target_size / 2.0;
+    y_axis.length()
+}
+
+/// Generates a quad for every glyph of `layout`, span colors are multiplied with
+/// `color`.
+#[inline]
+pub(crate) fn text_batches(
+    instance: &RenderInstance,
+    cache: &mut GlyphCache,
+    layout: &TextLayout,
+    color: [f32; 4],
+    pixels_per_unit: f32,
+) -> Vec<TextBatch> {
+    let spans: Vec<_> = layout
+        .spans
+        .iter()
+        .map(|span| {
+            // rasterize at whole pixel sizes to share glyphs between similar sizes
+            let px_size = (span.size * pixels_per_unit)
+                .round()
+                .max(1.0)
+                .min(MAX_PIXEL_SIZE);
+
+            (
+                span.font.chain(),
+                px_size,
+                span.size / px_size,
+                tint(span.color, color),
+            )
+        })
+        .collect();
+
+    let mut batches: Vec<TextBatch> = Vec::new();
+
+    for glyph in &layout.glyphs {
+        let (fonts, px_size, units_per_pixel, color) = &spans[glyph.span];
+
+        let position = glyph.position + glyph.offset;
+        // glyph positions are y up, the cache is y down
+        let pen = Vec2::new(position.x, -position.y) / *units_per_pixel;
+        let (whole, subpixel) = GlyphCache::snap(pen.x);
+
+        let cached = cache.glyph(
+            instance,
+            fonts[glyph.font],
+            GlyphId(glyph.glyph_id),
+            *px_size,
+            subpixel,
+        );
+
+        if let Some(cached) = cached {
+            push_glyph(
+                &mut batches,
+                cache,
+                &cached,
+                Vec2::new(whole, pen.y.round()),
+                *units_per_pixel,
+                *color,
+            );
+        }
+    }
+
+    batches
+}
+
+/// Vertices and indices of the decorations of `layout` matching `filter`, for the
+/// primary pipeline.
+#[inline]
+pub(crate) fn decoration_mesh(
+    layout: &TextLayout,
+    color: [f32; 4],
+    filter: impl Fn(TextDecorationKind) -> bool,
+) -> (Vec<[f32; 7]>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for decoration in &layout.decorations {
+        if !filter(decoration.kind) {
+            continue;
+        }
+
+        let [r, g, b, a] = tint(decoration.color, color);
+        let Rect { min, max } = decoration.rect;
+
+        let base = vertices.len() as u32;
+        vertices.extend_from_slice(&[
+            [min.x, min.y, 0.0, r, g, b, a],
+            [max.x, min.y, 0.0, r, g, b, a],
+            [max.x, max.y, 0.0, r, g, b, a],
+            [min.x, max.y, 0.0, r, g, b, a],
+        ]);
+        indices.extend(SPRITE_INDICES.iter().map(|i| base + i));
+    }
+
+    (vertices, indices)
+}
+
+#[inline]
+fn tint(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+/// Pushes the quad of a glyph with its pen position at `pen` pixels, y pointing down.
+#[inline]
+pub(crate) fn push_glyph(
+    batches: &mut Vec<TextBatch>,
+    cache: &GlyphCache,
+    glyph: &CachedGlyph,
+    pen: Vec2,
+    units_per_pixel: f32,
+    color: [f32; 4],
+) {
+    let entry = cache.atlas().get(glyph.region);
+
+    let top_left = pen + glyph.offset;
+    let bottom_right = top_left + glyph.size;
+
+    // flip to y pointing up
+    let rect = Rect::new(
+        Vec2::new(top_left.x, -bottom_right.y) * units_per_pixel,
+        Vec2::new(bottom_right.x, -top_left.y) * units_per_pixel,
+    );
+
+    let options = SpriteOptions::default().tint(color).source(entry.uv);
+
+    let batch = match batches
+        .iter_mut()
+        .position(|batch| batch.page == entry.page)
+    {
+        Some(i) => &mut batches[i],
+        None => {
+            batches.push(TextBatch {
+                page: entry.page,
+                ..Default::default()
+            });
+
+            batches.last_mut().unwrap()
+        }
+    };
+
+    let base = batch.vertices.len() as u32;
+    batch
+        .vertices
+        .extend_from_slice(&sprite_vertices(&rect, &options));
+    batch
+        .indices
+        .extend(SPRITE_INDICES.iter().map(|i| base + i));
+}
@@ -0,0 +1,1215 @@
+use std::{cmp::Ordering, ops::Range};
+
+use ab_glyph::{Font as _, ScaleFont};
+use glam::Vec2;
+use rustybuzz::{Direction, UnicodeBuffer};
+use unicode_bidi::BidiInfo;
+use unicode_script::{Script, UnicodeScript};
+
+use crate::{Font, Rect};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    /// Stretch the spaces of wrapped lines to fill the max width, the last line of a
+    /// paragraph is left aligned.
+    Justify,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerticalAlign {
+    /// Top of the first line at the origin.
+    Top,
+    /// Middle of the text at the origin.
+    Middle,
+    /// Bottom of the last line at the origin.
+    Bottom,
+    /// Baseline of the first line at the origin.
+    Baseline,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLayoutOptions {
+    /// Width lines are wrapped at, in local units.
+    ///
+    /// Horizontal alignment is relative to `0..max_width` when set and to the origin
+    /// otherwise.
+    pub max_width: Option<f32>,
+    pub align: TextAlign,
+    pub vertical_align: VerticalAlign,
+    /// Multiplier of the line height of the font.
+    pub line_height: f32,
+    /// Extra space added after every glyph, in local units.
+    pub letter_spacing: f32,
+}
+
+impl Default for TextLayoutOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            align: TextAlign::Left,
+            vertical_align: VerticalAlign::Baseline,
+            line_height: 1.0,
+            letter_spacing: 0.0,
+        }
+    }
+}
+
+impl TextLayoutOptions {
+    #[inline]
+    pub fn max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    #[inline]
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    #[inline]
+    pub fn vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+
+    #[inline]
+    pub fn line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    #[inline]
+    pub fn letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.letter_spacing = letter_spacing;
+        self
+    }
+}
+
+/// A piece of text with its own style, see [`TextLayout::rich`].
+#[derive(Clone, Debug)]
+pub struct TextSpan {
+    pub text: String,
+    pub font: Font,
+    /// Height of the font in local units.
+    pub size: f32,
+    /// Multiplied with the color the layout is drawn with.
+    pub color: [f32; 4],
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// Color of the highlight behind the span.
+    pub background: Option<[f32; 4]>,
+}
+
+impl TextSpan {
+    #[inline]
+    pub fn new(text: impl Into<String>, size: f32) -> Self {
+        Self {
+            text: text.into(),
+            font: Font::default(),
+            size,
+            color: [1.0; 4],
+            underline: false,
+            strikethrough: false,
+            background: None,
+        }
+    }
+
+    #[inline]
+    pub fn font(mut self, font: &Font) -> Self {
+        self.font = font.clone();
+        self
+    }
+
+    #[inline]
+    pub fn color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[inline]
+    pub fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    #[inline]
+    pub fn strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+
+    #[inline]
+    pub fn background(mut self, color: [f32; 4]) -> Self {
+        self.background = Some(color);
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionedGlyph {
+    pub glyph_id: u16,
+    /// Index of the span in [`TextLayout::spans`].
+    pub span: usize,
+    /// Index of the font in [`Font::chain`] of the span font.
+    pub font: usize,
+    /// Byte index of the cluster the glyph belongs to in the text.
+    pub index: usize,
+    /// Byte length of the cluster.
+    pub len: usize,
+    pub line: usize,
+    /// Pen position on the baseline in local units, y pointing up.
+    pub position: Vec2,
+    /// Offset of the glyph from the pen position, used to place marks.
+    pub offset: Vec2,
+    pub advance: f32,
+    /// Whether the glyph is part of a right to left run.
+    pub rtl: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLine {
+    /// Range into [`TextLayout::glyphs`], glyphs are in visual order.
+    pub glyphs: Range<usize>,
+    /// Byte range of the line in the text, excluding the line break.
+    pub text: Range<usize>,
+    pub baseline: f32,
+    pub bounds: Rect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextDecorationKind {
+    /// Drawn behind the glyphs.
+    Background,
+    Underline,
+    Strikethrough,
+}
+
+/// A solid rectangle drawn with the text, like an underline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextDecoration {
+    pub kind: TextDecorationKind,
+    pub rect: Rect,
+    pub color: [f32; 4],
+}
+
+/// Text laid out into lines of positioned glyphs, see [`TextLayout::new`].
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    /// Text of all spans.
+    pub text: String,
+    pub spans: Vec<TextSpan>,
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<TextLine>,
+    pub decorations: Vec<TextDecoration>,
+    /// Bounds of all lines, from the top of the first line to the bottom of the last.
+    pub bounds: Rect,
+}
+
+/// A shaped glyph before it is placed on a line, in logical order.
+#[derive(Clone, Copy, Debug)]
+struct Item {
+    glyph: u16,
+    span: usize,
+    font: usize,
+    /// Byte index of the cluster in the paragraph.
+    index: usize,
+    len: usize,
+    advance: f32,
+    offset: Vec2,
+    whitespace: bool,
+}
+
+/// Vertical metrics of a span, or the largest of a line.
+#[derive(Clone, Copy, Debug)]
+struct LineMetrics {
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+}
+
+impl LineMetrics {
+    #[inline]
+    fn new(span: &TextSpan) -> Self {
+        let scaled = span.font.inner().as_scaled(span.size);
+
+        Self {
+            ascent: scaled.ascent(),
+            descent: scaled.descent(),
+            line_gap: scaled.line_gap(),
+        }
+    }
+
+    #[inline]
+    fn max(self, other: Self) -> Self {
+        Self {
+            ascent: self.ascent.max(other.ascent),
+            descent: self.descent.min(other.descent),
+            line_gap: self.line_gap.max(other.line_gap),
+        }
+    }
+}
+
+impl TextLayout {
+    /// Lays out `text` with `font` at `size` local units.
+    ///
+    /// Text is shaped, so ligatures, marks and contextual forms are applied, and
+    /// reordered per line with the Unicode bidirectional algorithm. Glyphs missing from
+    /// `font` are taken from its fallbacks.
+    #[inline]
+    pub fn new(text: &str, font: &Font, size: f32, options: &TextLayoutOptions) -> Self {
+        Self::rich(&[TextSpan::new(text, size).font(font)], options)
+    }
+
+    /// Bounds of `text` laid out with [`TextLayout::new`], used to size boxes around
+    /// labels. Keep the layout instead when the glyph positions are needed too.
+    #[inline]
+    pub fn measure(text: &str, font: &Font, size: f32, options: &TextLayoutOptions) -> Rect {
+        Self::new(text, font, size, options).bounds
+    }
+
+    /// Lays out `spans` as one piece of text, lines wrap across spans and share a
+    /// baseline fitting the largest span on them.
+    #[inline]
+    pub fn rich(spans: &[TextSpan], options: &TextLayoutOptions) -> Self {
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+
+        // byte range of every span in `text`
+        let mut span_ranges = Vec::with_capacity(spans.len());
+        let mut span_start = 0;
+
+        for span in spans {
+            span_ranges.push(span_start..span_start + span.text.len());
+            span_start += span.text.len();
+        }
+
+        // span to take line metrics from on empty lines
+        let span_at = |index: usize| {
+            span_ranges
+                .iter()
+                .position(|range| index < range.end)
+                .unwrap_or(spans.len().saturating_sub(1))
+        };
+
+        let chains: Vec<_> = spans.iter().map(|span| span.font.chain()).collect();
+        let metrics: Vec<_> = spans.iter().map(LineMetrics::new).collect();
+
+        let mut glyphs = Vec::new();
+        let mut lines = Vec::new();
+        let mut decorations = Vec::new();
+        let mut previous_line: Option<(f32, LineMetrics)> = None;
+        let mut first_ascent = 0.0;
+        let mut paragraph_start = 0;
+
+        for paragraph in text.split('\n') {
+            let paragraph_range = paragraph_start..paragraph_start + paragraph.len();
+            let bidi = BidiInfo::new(paragraph, None);
+
+            let mut items = Vec::new();
+
+            for (range, rtl) in runs(paragraph, &bidi) {
+                // shape the part of the run covered by each span
+                for (i, span_range) in span_ranges.iter().enumerate() {
+                    let start = span_range.start.max(paragraph_range.start + range.start);
+                    let end = span_range.end.min(paragraph_range.start + range.end);
+
+                    if start >= end {
+                        continue;
+                    }
+
+                    shape(
+                        paragraph,
+                        start - paragraph_range.start..end - paragraph_range.start,
+                        rtl,
+                        i,
+                        &chains[i],
+                        0,
+                        spans[i].size,
+                        options.letter_spacing,
+                        &mut items,
+                    );
+                }
+            }
+
+            let breaks = wrap(&items, options.max_width);
+
+            for (i, range) in breaks.iter().enumerate() {
+                let last = i == breaks.len() - 1;
+
+                let text_start = items
+                    .get(range.start)
+                    .map_or(paragraph.len(), |item| item.index);
+                let text_end = items
+                    .get(range.end)
+                    .map_or(paragraph.len(), |item| item.index);
+
+                let line_items = &items[range.clone()];
+
+                let line_metrics = line_items
+                    .iter()
+                    .map(|item| metrics[item.span])
+                    .fold(None, |line: Option<LineMetrics>, span| {
+                        Some(line.map_or(span, |line| line.max(span)))
+                    })
+                    .or_else(|| {
+                        metrics
+                            .get(span_at(paragraph_range.start + text_start))
+                            .copied()
+                    })
+                    .unwrap_or(LineMetrics {
+                        ascent: 0.0,
+                        descent: 0.0,
+                        line_gap: 0.0,
+                    });
+
+                let baseline = match previous_line {
+                    Some((baseline, previous)) => {
+                        baseline
+                            - (line_metrics.ascent - previous.descent + previous.line_gap)
+                                * options.line_height
+                    }
+                    None => {
+                        first_ascent = line_metrics.ascent;
+                        0.0
+                    }
+                };
+
+                previous_line = Some((baseline, line_metrics));
+
+                // trailing whitespace doesn't take up space
+                let visible = line_items.len()
+                    - line_items
+                        .iter()
+                        .rev()
+                        .take_while(|item| item.whitespace)
+                        .count();
+                let width = line_width(&line_items[..visible]);
+
+                let spaces = line_items[..visible]
+                    .iter()
+                    .filter(|item| item.whitespace)
+                    .count();
+
+                let justify = match (options.align, options.max_width) {
+                    (TextAlign::Justify, Some(max_width)) if !last && spaces > 0 => {
+                        (max_width - width).max(0.0) / spaces as f32
+                    }
+                    _ => 0.0,
+                };
+
+                let x = match (options.align, options.max_width) {
+                    (TextAlign::Left, _) | (TextAlign::Justify, _) => 0.0,
+                    (TextAlign::Center, Some(max_width)) => (max_width - width) / 2.0,
+                    (TextAlign::Center, None) => -width / 2.0,
+                    (TextAlign::Right, Some(max_width)) => max_width - width,
+                    (TextAlign::Right, None) => -width,
+                };
+
+                // place the glyphs in visual order, then move the visible ones to `x`,
+                // trailing whitespace may end up on either side
+                let mut placed = Vec::with_capacity(line_items.len());
+                let mut pen = 0.0;
+                let mut visible_start = None;
+
+                for (j, rtl) in visual_order(&bidi, &items, range.clone(), text_start..text_end) {
+                    let item = &items[j];
+                    let trailing = j >= range.start + visible;
+
+                    if !trailing && visible_start.is_none() {
+                        visible_start = Some(pen);
+                    }
+
+                    placed.push((item, pen, rtl, trailing));
+                    pen += item.advance;
+
+                    if item.whitespace && !trailing {
+                        pen += justify;
+                    }
+                }
+
+                let shift = x - visible_start.unwrap_or(0.0);
+                let first_glyph = glyphs.len();
+
+                for &(item, pen, rtl, _) in &placed {
+                    glyphs.push(PositionedGlyph {
+                        glyph_id: item.glyph,
+                        span: item.span,
+                        font: item.font,
+                        index: paragraph_range.start + item.index,
+                        len: item.len,
+                        line: lines.len(),
+                        position: Vec2::new(pen + shift, baseline),
+                        offset: item.offset,
+                        advance: item.advance,
+                        rtl,
+                    });
+                }
+
+                let bounds_width = if justify > 0.0 {
+                    options.max_width.unwrap_or(width)
+                } else {
+                    width
+                };
+
+                let bounds = Rect::new(
+                    Vec2::new(x, baseline + line_metrics.descent),
+                    Vec2::new(x + bounds_width, baseline + line_metrics.ascent),
+                );
+
+                decorate(spans, &placed, shift, baseline, &bounds, &mut decorations);
+
+                lines.push(TextLine {
+                    glyphs: first_glyph..glyphs.len(),
+                    text: paragraph_range.start + text_start..paragraph_range.start + text_end,
+                    baseline,
+                    bounds,
+                });
+            }
+
+            // skip the line break
+            paragraph_start = paragraph_range.end + 1;
+        }
+
+        let mut layout = Self {
+            text,
+            spans: spans.to_vec(),
+            glyphs,
+            lines,
+            decorations,
+            bounds: Rect::default(),
+        };
+
+        layout.bounds = layout
+            .lines
+            .iter()
+            .map(|line| line.bounds)
+            .fold(None, |bounds: Option<Rect>, line| {
+                Some(bounds.map_or(line, |bounds| bounds.union(&line)))
+            })
+            .unwrap_or_default();
+
+        let offset = match options.vertical_align {
+            VerticalAlign::Top => -first_ascent,
+            VerticalAlign::Middle => -layout.bounds.center().y,
+            VerticalAlign::Bottom => -layout.bounds.min.y,
+            VerticalAlign::Baseline => 0.0,
+        };
+
+        layout.translate(Vec2::new(0.0, offset));
+
+        layout
+    }
+
+    #[inline]
+    fn translate(&mut self, offset: Vec2) {
+        for glyph in &mut self.glyphs {
+            glyph.position += offset;
+        }
+
+        for line in &mut self.lines {
+            line.baseline += offset.y;
+            line.bounds.min += offset;
+            line.bounds.max += offset;
+        }
+
+        for decoration in &mut self.decorations {
+            decoration.rect.min += offset;
+            decoration.rect.max += offset;
+        }
+
+        self.bounds.min += offset;
+        self.bounds.max += offset;
+    }
+
+    /// The font `glyph` is taken from.
+    #[inline]
+    pub fn glyph_font(&self, glyph: &PositionedGlyph) -> &Font {
+        self.spans[glyph.span].font.chain()[glyph.font]
+    }
+
+    /// Position of the caret before the character at byte `index`, on the baseline.
+    ///
+    /// In right to left runs the caret is on the right side of the character.
+    #[inline]
+    pub fn caret(&self, index: usize) -> Vec2 {
+        let line = self
+            .lines
+            .iter()
+            .find(|line| index <= line.text.end)
+            .or_else(|| self.lines.last());
+
+        let line = match line {
+            Some(line) => line,
+            None => return Vec2::ZERO,
+        };
+
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+        let leading_edge = |glyph: &PositionedGlyph| {
+            if glyph.rtl {
+                glyph.position.x + glyph.advance
+            } else {
+                glyph.position.x
+            }
+        };
+
+        // the glyphs of a cluster are adjacent, take the edge the cluster starts at
+        let cluster = glyphs
+            .iter()
+            .filter(|glyph| glyph.index + glyph.len > index)
+            .min_by_key(|glyph| glyph.index);
+
+        if let Some(cluster) = cluster {
+            let edges = glyphs
+                .iter()
+                .filter(|glyph| glyph.index == cluster.index)
+                .map(leading_edge);
+
+            let x = if cluster.rtl {
+                edges.fold(f32::MIN, f32::max)
+            } else {
+                edges.fold(f32::MAX, f32::min)
+            };
+
+            return Vec2::new(x, line.baseline);
+        }
+
+        // after the logically last glyph
+        match glyphs.iter().max_by_key(|glyph| glyph.index) {
+            Some(glyph) if glyph.rtl => Vec2::new(glyph.position.x, line.baseline),
+            Some(glyph) => Vec2::new(glyph.position.x + glyph.advance, line.baseline),
+            None => Vec2::new(line.bounds.min.x, line.baseline),
+        }
+    }
+
+    /// Byte index of the caret closest to `point`.
+    #[inline]
+    pub fn hit_test(&self, point: Vec2) -> usize {
+        let line = self.lines.iter().min_by(|a, b| {
+            let a = (a.bounds.center().y - point.y).abs();
+            let b = (b.bounds.center().y - point.y).abs();
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        });
+
+        let line = match line {
+            Some(line) => line,
+            None => return 0,
+        };
+
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+
+        for glyph in glyphs {
+            if point.x < glyph.position.x + glyph.advance / 2.0 {
+                // the left half of a right to left glyph is after it
+                return if glyph.rtl {
+                    glyph.index + glyph.len
+                } else {
+                    glyph.index
+                };
+            }
+        }
+
+        match glyphs.last() {
+            Some(glyph) if glyph.rtl => glyph.index,
+            _ => line.text.end,
+        }
+    }
+}
+
+/// Splits `text` into runs of the same direction and script, returning the byte range
+/// of each run and whether it's right to left.
+///
+/// Common characters like spaces and punctuation join the run they're in.
+#[inline]
+fn runs(text: &str, bidi: &BidiInfo) -> Vec<(Range<usize>, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut level = None;
+    let mut script = None;
+
+    for (i, c) in text.char_indices() {
+        let char_level = bidi.levels[i];
+        let char_script = match c.script() {
+            Script::Common | Script::Inherited | Script::Unknown => None,
+            char_script => Some(char_script),
+        };
+
+        let script_changed = matches!(
+            (script, char_script),
+            (Some(script), Some(char_script)) if script != char_script
+        );
+
+        if let Some(run_level) = level {
+            if run_level != char_level || script_changed {
+                runs.push((start..i, run_level.is_rtl()));
+
+                start = i;
+                script = None;
+            }
+        }
+
+        level = Some(char_level);
+
+        if char_script.is_some() {
+            script = char_script;
+        }
+    }
+
+    if let Some(level) = level {
+        runs.push((start..text.len(), level.is_rtl()));
+    }
+
+    runs
+}
+
+/// Shapes `text[range]` with `fonts[font]`, clusters the font has no glyphs for are
+/// shaped again with the next font in the chain.
+#[allow(clippy::too_many_arguments)]
+#[inline]
+fn shape(
+    text: &str,
+    range: Range<usize>,
+    rtl: bool,
+    span: usize,
+    fonts: &[&Font],
+    font: usize,
+    size: f32,
+    letter_spacing: f32,
+    items: &mut Vec<Item>,
+) {
+    let face = match rustybuzz::Face::from_slice(fonts[font].data(), 0) {
+        Some(face) => face,
+        None => return,
+    };
+
+    // ab_glyph scales glyphs to ascent minus descent, not the em
+    let scale = size / fonts[font].inner().height_unscaled();
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(&text[range.clone()]);
+    buffer.set_direction(if rtl {
+        Direction::RightToLeft
+    } else {
+        Direction::LeftToRight
+    });
+    buffer.guess_segment_properties();
+
+    let output = rustybuzz::shape(&face, &[], buffer);
+
+    let mut glyphs: Vec<_> = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .collect();
+
+    // right to left runs come out in visual order
+    if rtl {
+        glyphs.reverse();
+    }
+
+    // (glyphs, byte start of the cluster in `text`)
+    let mut clusters = Vec::new();
+    let mut start = 0;
+
+    while start < glyphs.len() {
+        let cluster = glyphs[start].0.cluster;
+        let end = start
+            + glyphs[start..]
+                .iter()
+                .take_while(|(info, _)| info.cluster == cluster)
+                .count();
+
+        clusters.push((start..end, range.start + cluster as usize));
+        start = end;
+    }
+
+    let cluster_end = |i: usize| {
+        clusters
+            .get(i + 1)
+            .map_or(range.end, |(_, index): &(Range<usize>, usize)| *index)
+    };
+
+    let mut i = 0;
+
+    while i < clusters.len() {
+        let missing = |i: usize| {
+            glyphs[clusters[i].0.clone()]
+                .iter()
+                .any(|(info, _)| info.glyph_id == 0)
+        };
+
+        if missing(i) && font + 1 < fonts.len() {
+            let mut end = i + 1;
+
+            while end < clusters.len() && missing(end) {
+                end += 1;
+            }
+
+            shape(
+                text,
+                clusters[i].1..cluster_end(end - 1),
+                rtl,
+                span,
+                fonts,
+                font + 1,
+                size,
+                letter_spacing,
+                items,
+            );
+
+            i = end;
+            continue;
+        }
+
+        let (cluster_glyphs, index) = clusters[i].clone();
+        let len = cluster_end(i) - index;
+        let whitespace = text[index..]
+            .chars()
+            .next()
+            .map_or(false, char::is_whitespace);
+        let last = cluster_glyphs.end - 1;
+
+        for j in cluster_glyphs {
+            let (info, position) = glyphs[j];
+
+            let mut advance = position.x_advance as f32 * scale;
+
+            if j == last {
+                advance += letter_spacing;
+            }
+
+            items.push(Item {
+                glyph: info.glyph_id as u16,
+                span,
+                font,
+                index,
+                len,
+                advance,
+                offset: Vec2::new(position.x_offset as f32, position.y_offset as f32) * scale,
+                whitespace,
+            });
+        }
+
+        i += 1;
+    }
+}
+
+/// Adds the backgrounds and lines of the spans on a line, `placed` are the items of the
+/// line in visual order with their pen position before `shift`.
+#[inline]
+fn decorate(
+    spans: &[TextSpan],
+    placed: &[(&Item, f32, bool, bool)],
+    shift: f32,
+    baseline: f32,
+    bounds: &Rect,
+    decorations: &mut Vec<TextDecoration>,
+) {
+    let mut i = 0;
+
+    while i < placed.len() {
+        let (item, pen, _, trailing) = placed[i];
+
+        // trailing whitespace isn't decorated
+        if trailing {
+            i += 1;
+            continue;
+        }
+
+        let end = i + placed[i..]
+            .iter()
+            .take_while(|(other, _, _, trailing)| other.span == item.span && !trailing)
+            .count();
+
+        let (last, last_pen, _, _) = placed[end - 1];
+        let span = &spans[item.span];
+
+        let min_x = pen + shift;
+        let max_x = last_pen + shift + last.advance;
+
+        let line = |(top, thickness): (f32, f32)| {
+            Rect::new(
+                Vec2::new(min_x, baseline + top - thickness),
+                Vec2::new(max_x, baseline + top),
+            )
+        };
+
+        if let Some(color) = span.background {
+            decorations.push(TextDecoration {
+                kind: TextDecorationKind::Background,
+                rect: Rect::new(
+                    Vec2::new(min_x, bounds.min.y),
+                    Vec2::new(max_x, bounds.max.y),
+                ),
+                color,
+            });
+        }
+
+        if span.underline {
+            decorations.push(TextDecoration {
+                kind: TextDecorationKind::Underline,
+                rect: line(span.font.underline(span.size)),
+                color: span.color,
+            });
+        }
+
+        if span.strikethrough {
+            decorations.push(TextDecoration {
+                kind: TextDecorationKind::Strikethrough,
+                rect: line(span.font.strikethrough(span.size)),
+                color: span.color,
+            });
+        }
+
+        i = end;
+    }
+}
+
+/// Returns the indices of `items[range]` in visual order, along with whether each is
+/// part of a right to left run.
+#[inline]
+fn visual_order(
+    bidi: &BidiInfo,
+    items: &[Item],
+    range: Range<usize>,
+    text: Range<usize>,
+) -> Vec<(usize, bool)> {
+    let paragraph = match bidi.paragraphs.first() {
+        Some(paragraph) if !text.is_empty() => paragraph,
+        _ => return range.map(|i| (i, false)).collect(),
+    };
+
+    let (levels, runs) = bidi.visual_runs(paragraph, text);
+    let mut order = Vec::with_capacity(range.len());
+
+    for run in runs {
+        let rtl = levels[run.start].is_rtl();
+        let start = order.len();
+
+        order.extend(
+            range
+                .clone()
+                .filter(|&i| run.contains(&items[i].index))
+                .map(|i| (i, rtl)),
+        );
+
+        if rtl {
+            order[start..].reverse();
+        }
+    }
+
+    order
+}
+
+/// Splits `items` into lines no wider than `max_width`, breaking after whitespace when
+/// possible and never inside a cluster.
+#[inline]
+fn wrap(items: &[Item], max_width: Option<f32>) -> Vec<Range<usize>> {
+    let max_width = match max_width {
+        Some(max_width) => max_width,
+        None => return vec![0..items.len()],
+    };
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut width = 0.0;
+    // index after the last whitespace on the current line
+    let mut break_at = None;
+
+    let mut i = 0;
+
+    while i < items.len() {
+        let item = &items[i];
+
+        // whitespace may hang past the max width
+        if !item.whitespace && width + item.advance > max_width && i > start {
+            let mut end = break_at.unwrap_or(i);
+
+            // move back to the start of the cluster
+            while end > start + 1 && items[end - 1].index == items[end].index {
+                end -= 1;
+            }
+
+            if items[end - 1].index != items[end].index {
+                lines.push(start..end);
+
+                start = end;
+                width = 0.0;
+                break_at = None;
+                i = end;
+                continue;
+            }
+        }
+
+        width += item.advance;
+
+        if item.whitespace {
+            break_at = Some(i + 1);
+        }
+
+        i += 1;
+    }
+
+    lines.push(start..items.len());
+    lines
+}
+
+#[inline]
+fn line_width(items: &[Item]) -> f32 {
+    items.iter().map(|item| item.advance).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = 20.0;
+    const TEXT: &str = "the quick brown fox jumps over the lazy dog";
+
+    fn layout(text: &str, options: &TextLayoutOptions) -> TextLayout {
+        TextLayout::new(text, &Font::default(), SIZE, options)
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    /// Right edge of the glyphs of `line` that aren't whitespace.
+    fn ink_end(layout: &TextLayout, line: &TextLine) -> f32 {
+        layout.glyphs[line.glyphs.clone()]
+            .iter()
+            .filter(|glyph| !layout.text[glyph.index..].starts_with(char::is_whitespace))
+            .map(|glyph| glyph.position.x + glyph.advance)
+            .fold(f32::MIN, f32::max)
+    }
+
+    /// Byte index of every caret position in `text`.
+    fn caret_indices(text: &str) -> Vec<usize> {
+        text.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .collect()
+    }
+
+    #[test]
+    fn advances_match_rasterized_glyphs() {
+        let font = Font::default();
+        let scaled = font.inner().as_scaled(SIZE);
+
+        // one character at a time so kerning doesn't change the advance
+        for c in "Hamburgefonstiv".chars() {
+            let layout = layout(&c.to_string(), &TextLayoutOptions::default());
+            let expected = scaled.h_advance(font.inner().glyph_id(c));
+
+            assert_eq!(layout.glyphs.len(), 1);
+            assert_close(layout.glyphs[0].advance, expected);
+        }
+    }
+
+    #[test]
+    fn wraps_at_max_width() {
+        let options = TextLayoutOptions::default().max_width(120.0);
+        let layout = layout(TEXT, &options);
+
+        assert!(layout.lines.len() > 1);
+
+        for line in &layout.lines {
+            assert!(line.bounds.width() <= 120.0 + 1e-3);
+            // lines break between words
+            assert!(!layout.text[line.text.clone()].starts_with(' '));
+        }
+
+        // every character ends up on exactly one line
+        let text: String = layout
+            .lines
+            .iter()
+            .map(|line| &layout.text[line.text.clone()])
+            .collect();
+        assert_eq!(text, TEXT);
+
+        // lines go down
+        for pair in layout.lines.windows(2) {
+            assert!(pair[1].baseline < pair[0].baseline);
+        }
+    }
+
+    #[test]
+    fn unwrapped_without_max_width() {
+        let layout = layout(TEXT, &TextLayoutOptions::default());
+
+        assert_eq!(layout.lines.len(), 1);
+    }
+
+    #[test]
+    fn aligns_without_max_width() {
+        let left = layout(
+            "label",
+            &TextLayoutOptions::default().align(TextAlign::Left),
+        );
+        let center = layout(
+            "label",
+            &TextLayoutOptions::default().align(TextAlign::Center),
+        );
+        let right = layout(
+            "label",
+            &TextLayoutOptions::default().align(TextAlign::Right),
+        );
+
+        assert_close(left.bounds.min.x, 0.0);
+        assert_close(center.bounds.center().x, 0.0);
+        assert_close(right.bounds.max.x, 0.0);
+
+        assert_close(left.bounds.width(), center.bounds.width());
+        assert_close(left.bounds.width(), right.bounds.width());
+    }
+
+    #[test]
+    fn aligns_within_max_width() {
+        let options = |align| TextLayoutOptions::default().max_width(200.0).align(align);
+
+        let left = layout("label", &options(TextAlign::Left));
+        let center = layout("label", &options(TextAlign::Center));
+        let right = layout("label", &options(TextAlign::Right));
+
+        assert_close(left.bounds.min.x, 0.0);
+        assert_close(center.bounds.center().x, 100.0);
+        assert_close(right.bounds.max.x, 200.0);
+    }
+
+    #[test]
+    fn justifies_all_but_the_last_line() {
+        let options = TextLayoutOptions::default()
+            .max_width(150.0)
+            .align(TextAlign::Justify);
+        let layout = layout(TEXT, &options);
+
+        let (last, lines) = layout.lines.split_last().unwrap();
+        assert!(!lines.is_empty());
+
+        for line in lines {
+            assert_close(line.bounds.min.x, 0.0);
+            assert_close(ink_end(&layout, line), 150.0);
+        }
+
+        assert_close(last.bounds.min.x, 0.0);
+        assert!(ink_end(&layout, last) < 150.0);
+    }
+
+    #[test]
+    fn line_height_scales_line_spacing() {
+        let spacing = |line_height| {
+            let options = TextLayoutOptions::default().line_height(line_height);
+            let layout = layout("one\ntwo", &options);
+
+            layout.lines[0].baseline - layout.lines[1].baseline
+        };
+
+        assert_close(spacing(2.0), spacing(1.0) * 2.0);
+    }
+
+    #[test]
+    fn letter_spacing_widens_lines() {
+        let width = |letter_spacing| {
+            let options = TextLayoutOptions::default().letter_spacing(letter_spacing);
+            TextLayout::measure("label", &Font::default(), SIZE, &options).width()
+        };
+
+        assert_close(width(2.0), width(0.0) + 2.0 * 5.0);
+    }
+
+    #[test]
+    fn vertical_align() {
+        let bounds = |vertical_align| {
+            let options = TextLayoutOptions::default().vertical_align(vertical_align);
+            TextLayout::measure("one\ntwo", &Font::default(), SIZE, &options)
+        };
+
+        assert_close(bounds(VerticalAlign::Middle).center().y, 0.0);
+        assert_close(bounds(VerticalAlign::Bottom).min.y, 0.0);
+        assert!(bounds(VerticalAlign::Top).max.y <= 1e-3);
+    }
+
+    #[test]
+    fn measure_matches_layout() {
+        let options = TextLayoutOptions::default().max_width(120.0);
+        let layout = layout(TEXT, &options);
+        let bounds = TextLayout::measure(TEXT, &Font::default(), SIZE, &options);
+
+        assert_eq!(bounds, layout.bounds);
+
+        let union = layout
+            .lines
+            .iter()
+            .skip(1)
+            .fold(layout.lines[0].bounds, |bounds, line| {
+                bounds.union(&line.bounds)
+            });
+        assert_eq!(bounds, union);
+
+        // the single line of a label is as wide as its glyphs
+        let layout = self::layout("label", &TextLayoutOptions::default());
+        let advance: f32 = layout.glyphs.iter().map(|glyph| glyph.advance).sum();
+        assert_close(layout.bounds.width(), advance);
+        assert!(layout.bounds.min.y < 0.0 && layout.bounds.max.y > 0.0);
+    }
+
+    #[test]
+    fn caret_hit_test_round_trip() {
+        let text = "hello world";
+        let layout = layout(text, &TextLayoutOptions::default());
+
+        let mut previous = f32::MIN;
+
+        for index in caret_indices(text) {
+            let caret = layout.caret(index);
+
+            assert!(caret.x > previous);
+            assert_eq!(layout.hit_test(caret), index);
+
+            previous = caret.x;
+        }
+    }
+
+    #[test]
+    fn caret_hit_test_round_trip_wrapped() {
+        let options = TextLayoutOptions::default().max_width(120.0);
+        let layout = layout(TEXT, &options);
+
+        for index in caret_indices(TEXT) {
+            assert_eq!(layout.hit_test(layout.caret(index)), index);
+        }
+    }
+
+    #[test]
+    fn caret_hit_test_round_trip_rtl() {
+        let text = "\u{5e9}\u{5dc}\u{5d5}\u{5dd} \u{5e2}\u{5d5}\u{5dc}\u{5dd}";
+        let layout = layout(text, &TextLayoutOptions::default());
+
+        assert!(layout.glyphs.iter().all(|glyph| glyph.rtl));
+
+        let mut previous = f32::MAX;
+
+        for index in caret_indices(text) {
+            let caret = layout.caret(index);
+
+            // right to left, carets move left as the index grows
+            assert!(caret.x < previous);
+            assert_eq!(layout.hit_test(caret), index);
+
+            previous = caret.x;
+        }
+    }
+
+    #[test]
+    fn hit_test_outside_text() {
+        let layout = layout("hello", &TextLayoutOptions::default());
+
+        assert_eq!(layout.hit_test(Vec2::new(-100.0, 0.0)), 0);
+        assert_eq!(layout.hit_test(Vec2::new(1000.0, 0.0)), 5);
+        // a degenerate viewport can produce nan points
+        layout.hit_test(Vec2::new(f32::NAN, f32::NAN));
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    num::NonZeroU32,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use glam::Vec2;
+
+use crate::{Rect, RenderInstance};
+
+static NEXT_TEXTURE_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FilterMode {
+    Nearest,
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddressMode {
+    /// Clamp uv coordinates to the edge of the texture.
+    Clamp,
+    /// Tile the texture.
+    Repeat,
+}
+
+/// How a [`Texture`] is sampled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Sampler {
+    pub filter: FilterMode,
+    pub address: AddressMode,
+}
+
+impl Sampler {
+    pub const LINEAR: Self = Self {
+        filter: FilterMode::Linear,
+        address: AddressMode::Clamp,
+    };
+
+    pub const NEAREST: Self = Self {
+        filter: FilterMode::Nearest,
+        address: AddressMode::Clamp,
+    };
+
+    #[inline]
+    pub fn repeat(mut self) -> Self {
+        self.address = AddressMode::Repeat;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn create(&self, instance: &RenderInstance) -> wgpu::Sampler {
+        let filter = match self.filter {
+            FilterMode::Nearest => wgpu::FilterMode::Nearest,
+            FilterMode::Linear => wgpu::FilterMode::Linear,
+        };
+
+        let address = match self.address {
+            AddressMode::Clamp => wgpu::AddressMode::ClampToEdge,
+            AddressMode::Repeat => wgpu::AddressMode::Repeat,
+        };
+
+        instance.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("texture sampler"),
+            address_mode_u: address,
+            address_mode_v: address,
+            address_mode_w: address,
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        })
+    }
+}
+
+impl Default for Sampler {
+    #[inline]
+    fn default() -> Self {
+        Self::LINEAR
+    }
+}
+
+/// An rgba texture on the gpu.
+#[derive(Debug)]
+pub struct Texture {
+    id: u64,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    width: u32,
+    height: u32,
+}
+
+impl Texture {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    /// Creates a texture from tightly packed rgba8 `data`.
+    #[inline]
+    pub fn from_rgba(instance: &RenderInstance, width: u32, height: u32, data: &[u8]) -> Self {
+        assert_eq!(
+            data.len(),
+            width as usize * height as usize * 4,
+            "texture data must be width * height * 4 bytes"
+        );
+
+        let texture = Self::empty(instance, width, height);
+        texture.write(instance, 0, 0, width, height, data);
+        texture
+    }
+
+    /// Creates a texture with undefined contents.
+    #[inline]
+    pub fn empty(instance: &RenderInstance, width: u32, height: u32) -> Self {
+        let texture = instance.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("texture"),
+            format: Self::FORMAT,
+            dimension: wgpu::TextureDimension::D2,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            sample_count: 1,
+            mip_level_count: 1,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            id: NEXT_TEXTURE_ID.fetch_add(1, Ordering::Relaxed),
+            texture,
+            view,
+            width,
+            height,
+        }
+    }
+
+    /// Decodes a png or jpeg image.
+    #[inline]
+    pub fn from_image_bytes(instance: &RenderInstance, bytes: &[u8]) -> anyhow::Result<Self> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+
+        Ok(Self::from_rgba(
+            instance,
+            image.width(),
+            image.height(),
+            &image,
+        ))
+    }
+
+    /// Loads and decodes a png or jpeg image from `path`.
+    #[inline]
+    pub fn open(instance: &RenderInstance, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let image = image::open(path)?.to_rgba8();
+
+        Ok(Self::from_rgba(
+            instance,
+            image.width(),
+            image.height(),
+            &image,
+        ))
+    }
+
+    /// Writes tightly packed rgba8 `data` to a region of the texture.
+    #[inline]
+    pub fn write(
+        &self,
+        instance: &RenderInstance,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        instance.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(width * 4),
+                rows_per_image: NonZeroU32::new(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Unique id of the texture, used to cache bind groups.
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    #[inline]
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+
+    /// Converts a rectangle in pixels, with the origin in the top left corner, to uv
+    /// coordinates.
+    #[inline]
+    pub fn uv_rect(&self, pixels: Rect) -> Rect {
+        Rect::new(pixels.min / self.size(), pixels.max / self.size())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::RenderInstance;
+
+/// A gpu buffer that per-frame data is packed into.
+///
+/// Data is pushed on the cpu during a frame and uploaded with a single
+/// [`flush`](UploadBuffer::flush). The buffer grows geometrically when it runs out of
+/// space and is shrunk again once it has been mostly unused for
+/// [`trim_frames`](UploadBuffer::trim_frames) frames in a row.
+#[derive(Debug)]
+pub struct UploadBuffer {
+    label: &'static str,
+    usage: wgpu::BufferUsage,
+    data: Vec<u8>,
+    buffer: wgpu::Buffer,
+    capacity: u64,
+    generation: u64,
+    idle_frames: u32,
+    pub trim_frames: u32,
+}
+
+impl UploadBuffer {
+    pub const MIN_CAPACITY: u64 = 1024;
+    pub const DEFAULT_TRIM_FRAMES: u32 = 120;
+
+    #[inline]
+    pub fn new(instance: &RenderInstance, label: &'static str, usage: wgpu::BufferUsage) -> Self {
+        Self {
+            label,
+            usage,
+            data: Vec::new(),
+            buffer: create_buffer(instance, label, usage, Self::MIN_CAPACITY),
+            capacity: Self::MIN_CAPACITY,
+            generation: 0,
+            idle_frames: 0,
+            trim_frames: Self::DEFAULT_TRIM_FRAMES,
+        }
+    }
+
+    /// Starts a new frame, discarding everything pushed in the last one.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.data.clear();
+    }
+
+    /// Pushes `data` at the next offset aligned to `alignment` and returns the offset.
+    #[inline]
+    pub fn push(&mut self, data: &[u8], alignment: u64) -> u64 {
+        let offset = align(self.data.len() as u64, alignment);
+
+        self.data.resize(offset as usize, 0);
+        self.data.extend_from_slice(data);
+
+        offset
+    }
+
+    /// Bytes pushed this frame.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Incremented every time the underlying buffer is recreated, bind groups
+    /// referencing the buffer must be recreated when this changes.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    #[inline]
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Uploads the data pushed this frame, resizing the buffer if needed.
+    #[inline]
+    pub fn flush(&mut self, instance: &RenderInstance) {
+        // writes must be a multiple of 4 bytes
+        let size = align(self.len(), wgpu::COPY_BUFFER_ALIGNMENT);
+        self.data.resize(size as usize, 0);
+
+        let required = size.max(Self::MIN_CAPACITY);
+
+        if required > self.capacity {
+            let mut capacity = self.capacity;
+
+            while capacity < required {
+                capacity *= 2;
+            }
+
+            self.recreate(instance, capacity);
+        } else if required <= self.capacity / 4 {
+            self.idle_frames += 1;
+
+            if self.idle_frames >= self.trim_frames {
+                self.recreate(instance, required.next_power_of_two());
+            }
+        } else {
+            self.idle_frames = 0;
+        }
+
+        if !self.data.is_empty() {
+            instance.queue.write_buffer(&self.buffer, 0, &self.data);
+        }
+    }
+
+    #[inline]
+    fn recreate(&mut self, instance: &RenderInstance, capacity: u64) {
+        self.buffer = create_buffer(instance, self.label, self.usage, capacity);
+        self.capacity = capacity;
+        self.generation += 1;
+        self.idle_frames = 0;
+    }
+}
+
+#[inline]
+fn align(offset: u64, alignment: u64) -> u64 {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+#[inline]
+fn create_buffer(
+    instance: &RenderInstance,
+    label: &'static str,
+    usage: wgpu::BufferUsage,
+    size: u64,
+) -> wgpu::Buffer {
+    instance.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: usage | wgpu::BufferUsage::COPY_DST,
+        mapped_at_creation: false,
+    })
+}